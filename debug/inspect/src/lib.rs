@@ -0,0 +1,184 @@
+//! Structured access to the fields of a value, as key/value pairs, without
+//! formatting it to a string first.
+//!
+//! ```text
+//! #[derive(Inspect)]
+//! pub struct Request {
+//!     method: &'static str,
+//!     status: u16,
+//!     #[debug(redact)]
+//!     token: String,
+//! }
+//!
+//! request.visit(&mut visitor)
+//!   -> visitor.visit_str("method", "GET")
+//!   -> visitor.visit_u64("status", 200)
+//!   -> visitor.visit_str("token", "[redacted]")
+//! ```
+
+use std::fmt::Debug;
+
+pub use derive_debug::Inspect;
+
+/// A value whose fields can be visited one by one.
+pub trait Inspect {
+    fn visit(&self, v: &mut dyn FieldVisitor);
+}
+
+/// Receives the fields of an [`Inspect`] value.
+///
+/// Only `visit_debug` is required, every other method falls back to it so a
+/// visitor only handles the kinds of values it cares about.
+pub trait FieldVisitor: AsFieldVisitor {
+    fn visit_debug(&mut self, name: &str, value: &dyn Debug);
+
+    fn visit_bool(&mut self, name: &str, value: bool) {
+        self.visit_debug(name, &value)
+    }
+
+    fn visit_i64(&mut self, name: &str, value: i64) {
+        self.visit_debug(name, &value)
+    }
+
+    fn visit_u64(&mut self, name: &str, value: u64) {
+        self.visit_debug(name, &value)
+    }
+
+    fn visit_f64(&mut self, name: &str, value: f64) {
+        self.visit_debug(name, &value)
+    }
+
+    fn visit_str(&mut self, name: &str, value: &str) {
+        self.visit_debug(name, &value)
+    }
+
+    /// A `None` option.
+    fn visit_none(&mut self, name: &str) {
+        self.visit_debug(name, &None::<()>)
+    }
+
+    /// A value that has fields of its own. By default its fields are visited
+    /// as if they belonged to the outer value.
+    fn visit_nested(&mut self, name: &str, value: &dyn Inspect) {
+        let _ = name;
+        value.visit(self.as_dyn());
+    }
+
+    /// A slice, `Vec`, ... By default every element is visited under the same
+    /// name.
+    fn visit_seq(&mut self, name: &str, values: &mut dyn Iterator<Item = &dyn InspectValue>) {
+        for value in values {
+            value.visit_value(name, self.as_dyn());
+        }
+    }
+}
+
+/// Lets the provided methods of [`FieldVisitor`] pass the visitor on, it is
+/// implemented for every visitor.
+pub trait AsFieldVisitor {
+    fn as_dyn(&mut self) -> &mut dyn FieldVisitor;
+}
+
+impl<V: FieldVisitor> AsFieldVisitor for V {
+    fn as_dyn(&mut self) -> &mut dyn FieldVisitor {
+        self
+    }
+}
+
+/// A field value, dispatched to the matching [`FieldVisitor`] method.
+///
+/// `#[derive(Inspect)]` bounds the type parameters of the struct by this trait
+/// and implements it for the struct itself, so the struct can be nested in
+/// other inspected structs.
+pub trait InspectValue {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor);
+}
+
+macro_rules! impl_inspect_value {
+    ($method:ident($as:ty) for $($ty:ty)*) => {
+        $(
+            impl InspectValue for $ty {
+                fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+                    v.$method(name, *self as $as)
+                }
+            }
+        )*
+    };
+}
+
+impl_inspect_value!(visit_bool(bool) for bool);
+impl_inspect_value!(visit_i64(i64) for i8 i16 i32 i64 isize);
+impl_inspect_value!(visit_u64(u64) for u8 u16 u32 u64 usize);
+impl_inspect_value!(visit_f64(f64) for f32 f64);
+
+impl InspectValue for i128 {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        v.visit_debug(name, self)
+    }
+}
+
+impl InspectValue for u128 {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        v.visit_debug(name, self)
+    }
+}
+
+impl InspectValue for char {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        v.visit_str(name, self.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl InspectValue for str {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        v.visit_str(name, self)
+    }
+}
+
+impl InspectValue for String {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        v.visit_str(name, self)
+    }
+}
+
+impl<T: InspectValue> InspectValue for Option<T> {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        match self {
+            Some(value) => value.visit_value(name, v),
+            None => v.visit_none(name),
+        }
+    }
+}
+
+impl<T: InspectValue> InspectValue for [T] {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        v.visit_seq(
+            name,
+            &mut self.iter().map(|value| value as &dyn InspectValue),
+        )
+    }
+}
+
+impl<T: InspectValue, const N: usize> InspectValue for [T; N] {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        self[..].visit_value(name, v)
+    }
+}
+
+impl<T: InspectValue> InspectValue for Vec<T> {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        self[..].visit_value(name, v)
+    }
+}
+
+impl<T: InspectValue + ?Sized> InspectValue for &T {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        (**self).visit_value(name, v)
+    }
+}
+
+impl<T: InspectValue + ?Sized> InspectValue for Box<T> {
+    fn visit_value(&self, name: &str, v: &mut dyn FieldVisitor) {
+        (**self).visit_value(name, v)
+    }
+}
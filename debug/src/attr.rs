@@ -0,0 +1,382 @@
+use quote::ToTokens;
+use syn::{punctuated::Punctuated, Token};
+
+/// Placeholder printed by `#[debug(redact)]` when no explicit one is given.
+const REDACTED: &str = "[redacted]";
+
+/// Options given by the caller on a field.
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    /// `#[debug = "..."]`
+    pub(crate) format: Option<syn::LitStr>,
+    /// `#[debug(skip)]`
+    pub(crate) skip: bool,
+    /// `#[debug(redact)]` or `#[debug(redact = "...")]`
+    pub(crate) redact: Option<String>,
+    /// `#[debug(with = "path::to::fmt_fn")]`
+    pub(crate) with: Option<syn::Path>,
+    /// `#[debug(bound = "...")]`
+    pub(crate) bound: Option<Vec<syn::WherePredicate>>,
+    /// `#[debug(rename = "...")]`
+    pub(crate) rename: Option<syn::LitStr>,
+    /// `#[debug(skip_if = "path::to::predicate")]`
+    pub(crate) skip_if: Option<syn::Path>,
+    /// `#[debug(truncate = N)]`
+    pub(crate) truncate: Option<usize>,
+}
+
+/// Options given by the caller on the struct.
+#[derive(Default)]
+pub(crate) struct StructAttrs {
+    /// `#[debug(bound = "...")]`
+    pub(crate) bound: Option<Vec<syn::WherePredicate>>,
+    /// `#[debug(finish_non_exhaustive)]`
+    pub(crate) finish_non_exhaustive: bool,
+    /// `#[debug(style = "...")]`
+    pub(crate) style: Option<Style>,
+    /// `#[debug(transparent)]`
+    pub(crate) transparent: bool,
+    /// `#[debug(opaque)]`
+    pub(crate) opaque: bool,
+    /// `#[debug(with = "path::to::fmt_fn")]`
+    pub(crate) with: Option<syn::Path>,
+}
+
+/// How the fields of the struct are laid out, given by `#[debug(style = "...")]`.
+/// A struct with named fields defaults to `Struct`, a tuple struct to `Tuple`.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Style {
+    /// `Name { a: 1, b: 2 }`, or one field per line with `{:#?}`.
+    Struct,
+    /// `Name { a: 1, b: 2 }`, even with `{:#?}`.
+    Flat,
+    /// `Name a=1 b=2`, even with `{:#?}`.
+    Compact,
+    /// `Name(1, 2)`, or one field per line with `{:#?}`.
+    Tuple,
+}
+
+// ```rust, ignore
+// #[debug = "0b{:08b}"]
+// bitmask: u8,
+//
+// #[debug(redact = "***")]
+// password: String,
+//
+// #[debug(with = "fmt_millis")]
+// timeout: Duration,
+//
+// #[debug(bound = "T::Value: Debug")]
+// field: Field<T>,
+//
+// #[debug(rename = "id")]
+// user_id: u64,
+//
+// #[debug(skip_if = "Option::is_none")]
+// parent: Option<u64>,
+//
+// #[debug(truncate = 16)]
+// samples: Vec<f64>,
+// ```
+//
+// Every `debug` attribute on the field is merged into a single `FieldAttrs`,
+// any other attribute (`#[doc]`, `#[serde]`, ...) is ignored.
+pub(crate) fn get_field_attrs(field: &syn::Field) -> Result<FieldAttrs, syn::Error> {
+    fn attr_error<T: ToTokens>(tokens: T) -> syn::Error {
+        syn::Error::new_spanned(
+            tokens,
+            r#"expected `debug = ".."`, `debug(skip)`, `debug(redact = "..")`, `debug(with = "..")`, `debug(bound = "..")`, `debug(rename = "..")`, `debug(skip_if = "..")` or `debug(truncate = N)`"#,
+        )
+    }
+
+    let mut field_attrs = FieldAttrs::default();
+    // Name of the option that decides how the value is printed, only one of them
+    // can be given per field.
+    let mut printed_by: Option<&str> = None;
+
+    for meta in debug_metas(&field.attrs)? {
+        match meta {
+            syn::Meta::NameValue(name_value) => {
+                set_exclusive(&mut printed_by, "debug = \"..\"", &name_value)?;
+                field_attrs.format = Some(lit_str(&name_value.lit)?.clone());
+            }
+            syn::Meta::List(meta_list) => {
+                if meta_list.nested.is_empty() {
+                    return Err(attr_error(meta_list));
+                }
+
+                for nested in &meta_list.nested {
+                    match nested {
+                        syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("skip") => {
+                            set_exclusive(&mut printed_by, "skip", path)?;
+                            field_attrs.skip = true;
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("redact") => {
+                            set_exclusive(&mut printed_by, "redact", path)?;
+                            field_attrs.redact = Some(String::from(REDACTED));
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::NameValue(name_value))
+                            if name_value.path.is_ident("redact") =>
+                        {
+                            set_exclusive(&mut printed_by, "redact", name_value)?;
+                            field_attrs.redact = Some(lit_str(&name_value.lit)?.value());
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::NameValue(name_value))
+                            if name_value.path.is_ident("with") =>
+                        {
+                            set_exclusive(&mut printed_by, "with", name_value)?;
+                            field_attrs.with = Some(lit_str(&name_value.lit)?.parse()?);
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::NameValue(name_value))
+                            if name_value.path.is_ident("bound") =>
+                        {
+                            if field_attrs.bound.is_some() {
+                                return Err(duplicate_error(name_value, "bound"));
+                            }
+                            field_attrs.bound = Some(where_predicates(&name_value.lit)?);
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::NameValue(name_value))
+                            if name_value.path.is_ident("rename") =>
+                        {
+                            if field_attrs.rename.is_some() {
+                                return Err(duplicate_error(name_value, "rename"));
+                            }
+                            field_attrs.rename = Some(lit_str(&name_value.lit)?.clone());
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::NameValue(name_value))
+                            if name_value.path.is_ident("skip_if") =>
+                        {
+                            if field_attrs.skip_if.is_some() {
+                                return Err(duplicate_error(name_value, "skip_if"));
+                            }
+                            field_attrs.skip_if = Some(lit_str(&name_value.lit)?.parse()?);
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::NameValue(name_value))
+                            if name_value.path.is_ident("truncate") =>
+                        {
+                            set_exclusive(&mut printed_by, "truncate", name_value)?;
+                            let limit = match &name_value.lit {
+                                syn::Lit::Int(limit) => limit,
+                                other => {
+                                    return Err(syn::Error::new_spanned(
+                                        other,
+                                        "expected an integer literal",
+                                    ))
+                                }
+                            };
+                            field_attrs.truncate = Some(limit.base10_parse()?);
+                        }
+                        _ => return Err(attr_error(nested)),
+                    }
+                }
+            }
+            syn::Meta::Path(path) => return Err(attr_error(path)),
+        }
+    }
+
+    if let (Some(skip_if), true) = (&field_attrs.skip_if, field_attrs.skip) {
+        return Err(syn::Error::new_spanned(
+            skip_if,
+            "`skip_if` cannot be combined with `skip`",
+        ));
+    }
+
+    Ok(field_attrs)
+}
+
+// ```rust, ignore
+// #[derive(CustomDebug)]
+// #[debug(bound = "T::Value: Debug, U: Debug", finish_non_exhaustive)]
+// pub struct Wrapper<T: Trait, U> { ... }
+//
+// #[derive(CustomDebug)]
+// #[debug(style = "flat")]
+// pub struct Point { ... }
+//
+// #[derive(CustomDebug)]
+// #[debug(transparent)]
+// pub struct UserId { id: u64 }
+//
+// #[derive(CustomDebug)]
+// #[debug(opaque)]
+// pub union Value { int: i64, float: f64 }
+//
+// #[derive(CustomDebug)]
+// #[debug(with = "fmt_tagged")]
+// pub union Tagged { ... }
+// ```
+pub(crate) fn get_struct_attrs(attrs: &[syn::Attribute]) -> Result<StructAttrs, syn::Error> {
+    fn attr_error<T: ToTokens>(tokens: T) -> syn::Error {
+        syn::Error::new_spanned(
+            tokens,
+            r#"expected `debug(bound = "..")`, `debug(finish_non_exhaustive)`, `debug(style = "..")`, `debug(transparent)`, `debug(opaque)` or `debug(with = "..")`"#,
+        )
+    }
+
+    let mut struct_attrs = StructAttrs::default();
+    // Name of the option that decides how the struct is laid out, only one of
+    // them can be given.
+    let mut printed_by: Option<&str> = None;
+    // The option that prints the whole value at once, which leaves no field
+    // list to finish.
+    let mut printed_whole: Option<proc_macro2::TokenStream> = None;
+
+    for meta in debug_metas(attrs)? {
+        let meta_list = match meta {
+            syn::Meta::List(meta_list) if !meta_list.nested.is_empty() => meta_list,
+            other => return Err(attr_error(other)),
+        };
+
+        for nested in &meta_list.nested {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::Path(path))
+                    if path.is_ident("finish_non_exhaustive") =>
+                {
+                    if struct_attrs.finish_non_exhaustive {
+                        return Err(duplicate_error(path, "finish_non_exhaustive"));
+                    }
+                    struct_attrs.finish_non_exhaustive = true;
+                }
+                syn::NestedMeta::Meta(syn::Meta::NameValue(name_value))
+                    if name_value.path.is_ident("bound") =>
+                {
+                    if struct_attrs.bound.is_some() {
+                        return Err(duplicate_error(name_value, "bound"));
+                    }
+                    struct_attrs.bound = Some(where_predicates(&name_value.lit)?);
+                }
+                syn::NestedMeta::Meta(syn::Meta::NameValue(name_value))
+                    if name_value.path.is_ident("style") =>
+                {
+                    set_exclusive(&mut printed_by, "style", name_value)?;
+                    let style = lit_str(&name_value.lit)?;
+                    struct_attrs.style = Some(match style.value().as_str() {
+                        "struct" => Style::Struct,
+                        "flat" => Style::Flat,
+                        "compact" => Style::Compact,
+                        "tuple" => Style::Tuple,
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                style,
+                                r#"expected one of "struct", "flat", "compact" or "tuple""#,
+                            ))
+                        }
+                    });
+                }
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("transparent") => {
+                    set_exclusive(&mut printed_by, "transparent", path)?;
+                    struct_attrs.transparent = true;
+                    printed_whole = Some(path.to_token_stream());
+                }
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("opaque") => {
+                    set_exclusive(&mut printed_by, "opaque", path)?;
+                    struct_attrs.opaque = true;
+                    printed_whole = Some(path.to_token_stream());
+                }
+                syn::NestedMeta::Meta(syn::Meta::NameValue(name_value))
+                    if name_value.path.is_ident("with") =>
+                {
+                    set_exclusive(&mut printed_by, "with", name_value)?;
+                    struct_attrs.with = Some(lit_str(&name_value.lit)?.parse()?);
+                    printed_whole = Some(name_value.to_token_stream());
+                }
+                _ => return Err(attr_error(nested)),
+            }
+        }
+    }
+
+    if let (Some(printed_whole), true) = (printed_whole, struct_attrs.finish_non_exhaustive) {
+        return Err(syn::Error::new_spanned(
+            printed_whole,
+            format!(
+                "`{}` cannot be combined with `finish_non_exhaustive`",
+                printed_by.unwrap_or_default()
+            ),
+        ));
+    }
+
+    Ok(struct_attrs)
+}
+
+// ```rust, ignore
+// #[derive(CustomDebug, Inspect)]
+// #[debug(bound = "T::Value: Debug")]
+// #[inspect(bound = "T::Value: InspectValue")]
+// pub struct Wrapper<T: Trait> { ... }
+// ```
+//
+// The bounds of `debug(bound = "..")` are written for Debug, so Inspect takes
+// its own from `inspect(bound = "..")`, on the struct or on a field.
+pub(crate) fn get_inspect_bound(
+    attrs: &[syn::Attribute],
+) -> Result<Option<Vec<syn::WherePredicate>>, syn::Error> {
+    fn attr_error<T: ToTokens>(tokens: T) -> syn::Error {
+        syn::Error::new_spanned(tokens, r#"expected `inspect(bound = "..")`"#)
+    }
+
+    let mut bound = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("inspect")) {
+        let meta_list = match attr.parse_meta()? {
+            syn::Meta::List(meta_list) if !meta_list.nested.is_empty() => meta_list,
+            other => return Err(attr_error(other)),
+        };
+        for nested in &meta_list.nested {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(name_value))
+                    if name_value.path.is_ident("bound") =>
+                {
+                    if bound.is_some() {
+                        return Err(duplicate_error(name_value, "bound"));
+                    }
+                    bound = Some(where_predicates(&name_value.lit)?);
+                }
+                _ => return Err(attr_error(nested)),
+            }
+        }
+    }
+    Ok(bound)
+}
+
+/// Parses every `#[debug...]` attribute, skipping over unrelated ones.
+fn debug_metas(attrs: &[syn::Attribute]) -> Result<Vec<syn::Meta>, syn::Error> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("debug"))
+        .map(|attr| attr.parse_meta())
+        .collect()
+}
+
+/// `#[debug = 5]` -> error: expected a string literal
+fn lit_str(lit: &syn::Lit) -> Result<&syn::LitStr, syn::Error> {
+    match lit {
+        syn::Lit::Str(lit_str) => Ok(lit_str),
+        other => Err(syn::Error::new_spanned(other, "expected a string literal")),
+    }
+}
+
+/// `"T: Debug, U::Item: Debug"` -> `[T: Debug, U::Item: Debug]`
+fn where_predicates(lit: &syn::Lit) -> Result<Vec<syn::WherePredicate>, syn::Error> {
+    let predicates =
+        lit_str(lit)?.parse_with(Punctuated::<syn::WherePredicate, Token![,]>::parse_terminated)?;
+    Ok(predicates.into_iter().collect())
+}
+
+/// Records the option that decides how a field is printed, rejecting a second one.
+fn set_exclusive<'a, T: ToTokens>(
+    printed_by: &mut Option<&'a str>,
+    option: &'a str,
+    tokens: T,
+) -> Result<(), syn::Error> {
+    match printed_by.replace(option) {
+        None => Ok(()),
+        Some(previous) if previous == option => Err(duplicate_error(tokens, option)),
+        Some(previous) => Err(syn::Error::new_spanned(
+            tokens,
+            format!("`{}` cannot be combined with `{}`", option, previous),
+        )),
+    }
+}
+
+fn duplicate_error<T: ToTokens>(tokens: T, option: &str) -> syn::Error {
+    syn::Error::new_spanned(tokens, format!("duplicate `{}` attribute", option))
+}
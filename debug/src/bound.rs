@@ -0,0 +1,151 @@
+use proc_macro2::Ident;
+use quote::ToTokens;
+use syn::{parse_quote, visit::Visit, Type};
+
+/// Bounds the generics of the derived impl by `trait_path`, e.g. `::core::fmt::Debug`.
+pub(crate) fn add_trait_bounds(
+    mut generics: syn::Generics,
+    trait_path: &syn::Path,
+    field_types: Vec<&Type>,
+    field_bounds: Vec<&syn::WherePredicate>,
+    bound_attr: Option<Vec<syn::WherePredicate>>,
+) -> syn::Generics {
+    if let Some(predicates) = bound_attr {
+        // `#[debug(bound = "")]` disables inference without adding any bound.
+        generics.make_where_clause().predicates.extend(predicates);
+    } else {
+        let generic_idents = generics
+            .type_params()
+            .map(|t| t.ident.clone())
+            .collect::<Vec<_>>();
+
+        let mut collector = BoundCollector {
+            generic_idents: &generic_idents,
+            type_params: Vec::new(),
+            types: Vec::new(),
+        };
+        for ty in field_types {
+            collector.visit_type(ty);
+        }
+        let BoundCollector {
+            type_params, types, ..
+        } = collector;
+
+        // impl <T: Debug, T2: Debug> Debug for Foo { ... }
+        for type_param in generics.type_params_mut() {
+            if type_params.contains(&type_param.ident) {
+                type_param.bounds.push(parse_quote!(#trait_path));
+            }
+        }
+
+        // impl <T: Debug, T2: Debug> Debug for Foo { ... } where <T3::Value>: Debug
+        let where_clause = generics.make_where_clause();
+        for ty in types {
+            // Notice, here we need to pass the whole type and not only the ident.
+            where_clause.predicates.push(parse_quote! {
+                #ty : #trait_path
+            })
+        }
+
+        // #[debug(bound = "...")] on a field replaces whatever would be inferred from it.
+        where_clause
+            .predicates
+            .extend(field_bounds.into_iter().cloned());
+    }
+    generics
+}
+
+/// Walks the type of each formatted field and collects the minimal set of
+/// types that need to implement the derived trait for the field to implement it.
+///
+/// ```text
+/// HashMap<K, V::Assoc>     -> K: Debug, V::Assoc: Debug
+/// &'a [(T, u8); 4]         -> T: Debug
+/// PhantomData<T>           -> (nothing)
+/// fn(T) -> U               -> (nothing)
+/// Box<dyn Trait<T>>        -> (nothing)
+/// ```
+struct BoundCollector<'a> {
+    generic_idents: &'a [Ident],
+    /// Type parameters used directly e.g. `T` or `Vec<T>`.
+    type_params: Vec<Ident>,
+    /// Types that have a type parameter as their root e.g. `T::Value` or `<T as Trait>::Value`.
+    types: Vec<Type>,
+}
+
+impl BoundCollector<'_> {
+    fn is_generic(&self, ident: &Ident) -> bool {
+        self.generic_idents.contains(ident)
+    }
+
+    /// Whether `ty` mentions any of the type parameters.
+    fn mentions_generic(&self, ty: &Type) -> bool {
+        fn mentions(tokens: proc_macro2::TokenStream, generic_idents: &[Ident]) -> bool {
+            tokens.into_iter().any(|token| match token {
+                proc_macro2::TokenTree::Ident(ref ident) => generic_idents.contains(ident),
+                proc_macro2::TokenTree::Group(group) => mentions(group.stream(), generic_idents),
+                _ => false,
+            })
+        }
+        mentions(ty.to_token_stream(), self.generic_idents)
+    }
+
+    fn push_type_param(&mut self, ident: &Ident) {
+        if !self.type_params.contains(ident) {
+            self.type_params.push(ident.clone());
+        }
+    }
+
+    fn push_type(&mut self, ty: &Type) {
+        if !self.types.contains(ty) {
+            self.types.push(ty.clone());
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for BoundCollector<'_> {
+    fn visit_type(&mut self, ty: &'ast Type) {
+        // Tip: eprintln! on the Type.
+        // NB. Make sure the test compiles to be able to emit the debug to the sdterr.
+        // eprintln!("{ty:#?}");
+
+        match ty {
+            // <T as Trait>::Value
+            Type::Path(type_path) if type_path.qself.is_some() => {
+                if self.mentions_generic(ty) {
+                    self.push_type(ty);
+                }
+            }
+            Type::Path(type_path) => {
+                let path = &type_path.path;
+                let first = &path.segments[0];
+                let last = &path.segments[path.segments.len() - 1];
+
+                if path.leading_colon.is_none() && self.is_generic(&first.ident) {
+                    if path.segments.len() == 1 {
+                        // T
+                        self.push_type_param(&first.ident);
+                    } else {
+                        // T::Value
+                        self.push_type(ty);
+                    }
+                } else if last.ident == "PhantomData" {
+                    // PhantomData<T> and std::marker::PhantomData<T> are Debug for any T.
+                } else {
+                    // HashMap<K, V> -> K, V
+                    syn::visit::visit_type_path(self, type_path);
+                }
+            }
+            // Function pointers and raw pointers are Debug regardless of the
+            // types they mention, and there is no way to express the bound a
+            // trait object would need in terms of the type parameters.
+            Type::BareFn(_)
+            | Type::Ptr(_)
+            | Type::TraitObject(_)
+            | Type::ImplTrait(_)
+            | Type::Macro(_) => {}
+            // &'a T, [T; N], [T], (T, U), (T)
+            _ => syn::visit::visit_type(self, ty),
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::bound::add_trait_bounds;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_quote, Data, DeriveInput, Fields, Ident, LitStr, Member};
+
+// ```rust, ignore
+// #[derive(CustomDisplay)]
+// #[display("conn {host}:{port} failed")]
+// pub struct ConnError {
+//     host: String,
+//     port: u16,
+// }
+//
+// impl Display for ConnError {
+//     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+//         write!(f, "conn {host}:{port} failed", host = &self.host, port = &self.port)
+//     }
+// }
+// ```
+pub(crate) fn expand(input: DeriveInput) -> Result<TokenStream, syn::Error> {
+    let DeriveInput {
+        attrs,
+        vis: _,
+        ident,
+        generics,
+        data,
+    } = input;
+
+    let mut field_types = Vec::new();
+
+    let body = match &data {
+        Data::Struct(strct) => {
+            let template = get_display_attr(&attrs)?.ok_or_else(|| {
+                syn::Error::new_spanned(&ident, r#"missing `#[display("...")]` attribute"#)
+            })?;
+            let (format, members) = parse_template(&template, &strct.fields)?;
+            field_types.extend(members_types(&members, &strct.fields));
+
+            let args = members.iter().map(|member| {
+                let arg = arg_name(member);
+                quote!(#arg = &self.#member)
+            });
+            quote!(::core::write!(f, #format #(, #args)*))
+        }
+        Data::Enum(enm) => {
+            if let Some(attr) = attrs.iter().find(|attr| attr.path.is_ident("display")) {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    r#"`#[display("...")]` goes on each variant of an enum"#,
+                ));
+            }
+
+            let arms = enm
+                .variants
+                .iter()
+                .map(|variant| {
+                    let variant_ident = &variant.ident;
+                    let template = match get_display_attr(&variant.attrs)? {
+                        Some(template) => template,
+                        // Unit variants default to their name.
+                        None if variant.fields.is_empty() => {
+                            LitStr::new(&variant_ident.to_string(), variant_ident.span())
+                        }
+                        None => {
+                            return Err(syn::Error::new_spanned(
+                                variant,
+                                r#"missing `#[display("...")]` attribute"#,
+                            ))
+                        }
+                    };
+                    let (format, members) = parse_template(&template, &variant.fields)?;
+                    field_types.extend(members_types(&members, &variant.fields));
+
+                    let args = members.iter().map(arg_name).collect::<Vec<_>>();
+                    let bindings = members.iter().map(binding_name).collect::<Vec<_>>();
+                    Ok(quote! {
+                        Self::#variant_ident { #(#members: #bindings,)* .. } => {
+                            ::core::write!(f, #format #(, #args = #bindings)*)
+                        }
+                    })
+                })
+                .collect::<Result<Vec<_>, syn::Error>>()?;
+
+            // An enum without variants can't be matched through a reference.
+            if arms.is_empty() {
+                quote!(match *self {})
+            } else {
+                quote! {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+        Data::Union(union) => {
+            return Err(syn::Error::new_spanned(
+                union.union_token,
+                "CustomDisplay is not supported for unions",
+            ))
+        }
+    };
+
+    let generics = add_trait_bounds(
+        generics,
+        &parse_quote!(::core::fmt::Display),
+        field_types,
+        Vec::new(),
+        None,
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::core::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #body
+            }
+        }
+    })
+}
+
+/// `#[display("...")]` -> Some("...")
+fn get_display_attr(attrs: &[syn::Attribute]) -> Result<Option<LitStr>, syn::Error> {
+    fn attr_error<T: ToTokens>(tokens: T) -> syn::Error {
+        syn::Error::new_spanned(tokens, r#"expected `display("...")`"#)
+    }
+
+    let mut template = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("display")) {
+        let meta_list = match attr.parse_meta()? {
+            syn::Meta::List(meta_list) if meta_list.nested.len() == 1 => meta_list,
+            other => return Err(attr_error(other)),
+        };
+
+        match &meta_list.nested[0] {
+            syn::NestedMeta::Lit(syn::Lit::Str(lit_str)) => {
+                if template.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "duplicate `display` attribute",
+                    ));
+                }
+                template = Some(lit_str.clone());
+            }
+            other => return Err(attr_error(other)),
+        }
+    }
+
+    Ok(template)
+}
+
+/// Checks that every placeholder of the template names a field and rewrites
+/// tuple fields into named arguments, since `format_args!` would read `{0}`
+/// as a positional argument.
+///
+/// ```text
+/// "{0} at {pos:>4}" -> ("{_0} at {pos:>4}", [0, pos])
+/// ```
+fn parse_template(template: &LitStr, fields: &Fields) -> Result<(String, Vec<Member>), syn::Error> {
+    let error = |message: String| syn::Error::new_spanned(template, message);
+
+    let value = template.value();
+    let mut format = String::new();
+    let mut members: Vec<Member> = Vec::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                format.push_str("{{");
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                format.push_str("}}");
+            }
+            '}' => return Err(error(String::from("unmatched `}` in display template"))),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => {
+                            return Err(error(String::from("unclosed `{` in display template")))
+                        }
+                    }
+                }
+
+                let (name, spec) = match placeholder.find(':') {
+                    Some(colon) => placeholder.split_at(colon),
+                    None => (placeholder.as_str(), ""),
+                };
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err(error(String::from(
+                        "positional `{}` is not supported, name a field e.g. `{host}` or `{0}`",
+                    )));
+                }
+
+                let member = find_member(name, fields).ok_or_else(|| {
+                    error(format!("unknown field `{}` in display template", name))
+                })?;
+                format.push('{');
+                format.push_str(&arg_name(&member).to_string());
+                format.push_str(spec);
+                format.push('}');
+
+                if !members.contains(&member) {
+                    members.push(member);
+                }
+            }
+            c => format.push(c),
+        }
+    }
+
+    Ok((format, members))
+}
+
+/// `"host"` -> `self.host`, `"0"` -> `self.0`
+fn find_member(name: &str, fields: &Fields) -> Option<Member> {
+    fields.iter().enumerate().find_map(|(index, field)| {
+        let member = match &field.ident {
+            Some(ident) if ident == name => Member::Named(ident.clone()),
+            None if index.to_string() == name => Member::Unnamed(index.into()),
+            _ => return None,
+        };
+        Some(member)
+    })
+}
+
+/// Name of the `format_args!` argument bound to a field: `host` or `_0`.
+fn arg_name(member: &Member) -> Ident {
+    match member {
+        Member::Named(ident) => ident.clone(),
+        Member::Unnamed(index) => format_ident!("_{}", index.index),
+    }
+}
+
+/// Name a variant field is bound to in its `match` arm, which can't be the
+/// field name since a field called `f` would hide the formatter.
+fn binding_name(member: &Member) -> Ident {
+    match member {
+        Member::Named(ident) => format_ident!("__field_{}", ident),
+        Member::Unnamed(index) => format_ident!("__field_{}", index.index),
+    }
+}
+
+fn members_types<'a>(members: &[Member], fields: &'a Fields) -> Vec<&'a syn::Type> {
+    fields
+        .iter()
+        .enumerate()
+        .filter(|(index, field)| {
+            members.iter().any(|member| match (member, &field.ident) {
+                (Member::Named(name), Some(ident)) => name == ident,
+                (Member::Unnamed(unnamed), None) => unnamed.index as usize == *index,
+                _ => false,
+            })
+        })
+        .map(|(_, field)| &field.ty)
+        .collect()
+}
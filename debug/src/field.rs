@@ -0,0 +1,181 @@
+use crate::attr::FieldAttrs;
+use crate::format::parse_field_format;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Member;
+
+/// `(label, value, skip condition)` of a field that is printed.
+pub(crate) type DebugField = (TokenStream, TokenStream, Option<TokenStream>);
+
+/// How each field is accessed on `self`: `self.host` or `self.0`.
+pub(crate) fn field_members<'a>(fields: impl IntoIterator<Item = &'a syn::Field>) -> Vec<Member> {
+    fields
+        .into_iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(index.into()),
+        })
+        .collect()
+}
+
+/// The fields that the format strings of the printed fields reference as
+/// `{self.other}`, which are formatted through Debug too.
+pub(crate) fn referenced_fields(
+    field_names: &[Member],
+    field_attrs: &[FieldAttrs],
+) -> Result<Vec<usize>, syn::Error> {
+    let mut referenced = Vec::new();
+    for attrs in field_attrs {
+        let format = match &attrs.format {
+            Some(format)
+                if !attrs.skip
+                    && attrs.redact.is_none()
+                    && attrs.with.is_none()
+                    && attrs.truncate.is_none() =>
+            {
+                parse_field_format(format, field_names, field_attrs)?
+            }
+            _ => continue,
+        };
+        for other in format.others() {
+            let index = field_names.iter().position(|name| name == other).unwrap();
+            if !referenced.contains(&index) {
+                referenced.push(index);
+            }
+        }
+    }
+    Ok(referenced)
+}
+
+/// Builds the label and the Debug value of every field that is not skipped,
+/// in declaration order.
+///
+/// ```text
+/// #[debug(rename = "id")] user_id: u64           -> ("id", self.user_id, None)
+/// #[debug = "0b{:08b}"] bits: u8                 -> (stringify!(bits), format_args!(..), None)
+/// #[debug(skip_if = "Option::is_none")] parent   -> (.., self.parent, Some(Option::is_none(&self.parent)))
+/// ```
+pub(crate) fn debug_fields(
+    field_names: &[Member],
+    field_attrs: &[FieldAttrs],
+) -> Result<Vec<DebugField>, syn::Error> {
+    let field_formats = field_attrs
+        .iter()
+        .map(|attrs| {
+            attrs
+                .format
+                .as_ref()
+                .map(|format| parse_field_format(format, field_names, field_attrs))
+                .transpose()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let debug_fields = field_names
+        .iter()
+        .zip(field_attrs)
+        .zip(&field_formats)
+        .filter_map(|((field_name, field_attrs), field_format)| {
+            if field_attrs.skip {
+                return None;
+            }
+
+            let label = match &field_attrs.rename {
+                Some(rename) => quote!(#rename),
+                None => quote!(::core::stringify!(#field_name)),
+            };
+
+            let value = match (
+                &field_attrs.redact,
+                &field_attrs.with,
+                field_attrs.truncate,
+                field_format,
+            ) {
+                (Some(placeholder), _, _, _) => {
+                    quote!(::core::format_args!("{}", #placeholder))
+                }
+                (None, Some(with), _, _) => quote!(__DebugWith(&self.#field_name, #with)),
+                (None, None, Some(limit), _) => quote!(__DebugTruncate(&self.#field_name, #limit)),
+                (None, None, None, Some(format)) => format.to_format_args(field_name),
+                (None, None, None, None) => quote!(self.#field_name),
+            };
+
+            let skip_if = field_attrs
+                .skip_if
+                .as_ref()
+                .map(|skip_if| quote!(#skip_if(&self.#field_name)));
+
+            Some((label, value, skip_if))
+        })
+        .collect();
+
+    Ok(debug_fields)
+}
+
+/// Helper types the values built by [`debug_fields`] rely on, declared only
+/// when some field needs them.
+pub(crate) fn adapters(field_attrs: &[FieldAttrs]) -> TokenStream {
+    // Adapts a `fn(&T, &mut Formatter) -> fmt::Result` into a Debug value.
+    let debug_with = if field_attrs
+        .iter()
+        .any(|field_attrs| field_attrs.with.is_some())
+    {
+        quote! {
+            struct __DebugWith<'a, T: ?Sized>(
+                &'a T,
+                fn(&T, &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result,
+            );
+
+            impl<'a, T: ?Sized> ::core::fmt::Debug for __DebugWith<'a, T> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    (self.1)(self.0, f)
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    // Prints the first N elements of anything iterable by reference, followed
+    // by the number of elements left out: `[1, 2, 3, ... (5 more)]`.
+    let debug_truncate = if field_attrs
+        .iter()
+        .any(|field_attrs| field_attrs.truncate.is_some())
+    {
+        quote! {
+            struct __DebugTruncate<'a, T: ?Sized>(&'a T, usize);
+
+            impl<'a, T: ?Sized> ::core::fmt::Debug for __DebugTruncate<'a, T>
+            where
+                &'a T: ::core::iter::IntoIterator,
+                <&'a T as ::core::iter::IntoIterator>::Item: ::core::fmt::Debug,
+            {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut iter = ::core::iter::IntoIterator::into_iter(self.0);
+                    let mut list = f.debug_list();
+                    list.entries(::core::iter::Iterator::by_ref(&mut iter).take(self.1));
+                    let more = ::core::iter::Iterator::count(iter);
+                    if more > 0 {
+                        list.entry(&::core::format_args!("... ({} more)", more));
+                    }
+                    list.finish()
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    quote! {
+        #debug_with
+        #debug_truncate
+    }
+}
+
+/// Wraps the statement printing a field in its `skip_if` condition, if any.
+pub(crate) fn when_printed(skip_if: &Option<TokenStream>, statement: TokenStream) -> TokenStream {
+    match skip_if {
+        Some(skip_if) => quote!(if !#skip_if { #statement }),
+        None => statement,
+    }
+}
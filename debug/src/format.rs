@@ -0,0 +1,229 @@
+use crate::attr::FieldAttrs;
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{LitStr, Member};
+
+/// A validated `#[debug = "..."]` format string.
+pub(crate) struct FieldFormat {
+    /// The format string, with `{self.other}` rewritten into a named argument.
+    format: String,
+    /// Other fields referenced as `{self.other}`.
+    others: Vec<Member>,
+}
+
+impl FieldFormat {
+    /// The other fields referenced as `{self.other}`.
+    pub(crate) fn others(&self) -> &[Member] {
+        &self.others
+    }
+
+    /// `format_args!("{:08b} of {__self_len}", &self.bits, __self_len = &self.len)`
+    pub(crate) fn to_format_args(&self, field_name: &Member) -> TokenStream {
+        let format = &self.format;
+        let args = self.others.iter().map(|other| {
+            let arg = other_arg(other);
+            quote!(#arg = &self.#other)
+        });
+        quote!(::core::format_args!(#format, &self.#field_name #(, #args)*))
+    }
+}
+
+// ```rust, ignore
+// #[debug = "0b{:08b}"]            ok
+// #[debug = "{0:#x} ({0})"]        ok, the value can be used more than once
+// #[debug = "{} of {self.len}"]    ok, other fields are referenced through `self`
+// #[debug = "0b{:08q}"]            error: unknown format trait `q`
+// #[debug = "{} {}"]               error: only one positional argument
+// #[debug = "{name}"]              error: named arguments are not supported
+// #[debug = "{} {self.password}"]  error: `password` is redacted or skipped
+// ```
+//
+// The field value is the only positional argument given to `format_args!`, so
+// reporting the mistakes here lets the error point at the attribute instead of
+// somewhere inside the macro expansion.
+pub(crate) fn parse_field_format(
+    lit: &LitStr,
+    field_names: &[Member],
+    field_attrs: &[FieldAttrs],
+) -> Result<FieldFormat, syn::Error> {
+    let error = |message: String| {
+        syn::Error::new(lit.span(), format!("invalid format string: {}", message))
+    };
+
+    let value = lit.value();
+    let mut format = String::new();
+    let mut others: Vec<Member> = Vec::new();
+    let mut next_implicit = 0;
+    let mut uses_value = false;
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                format.push_str("{{");
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                format.push_str("}}");
+            }
+            '}' => return Err(error(String::from("unmatched `}`"))),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(error(String::from("unclosed `{`"))),
+                    }
+                }
+
+                let (arg, spec) = match placeholder.find(':') {
+                    Some(colon) => (&placeholder[..colon], &placeholder[colon + 1..]),
+                    None => (placeholder.as_str(), ""),
+                };
+                let arg = arg.trim();
+                validate_spec(spec).map_err(error)?;
+
+                format.push('{');
+                if let Some(other) = arg.strip_prefix("self.") {
+                    let index = field_names
+                        .iter()
+                        .position(|field_name| match field_name {
+                            Member::Named(ident) => ident == other,
+                            Member::Unnamed(index) => index.index.to_string() == other,
+                        })
+                        .ok_or_else(|| error(format!("no field named `{}`", other)))?;
+                    // Printing it through another field would defeat the point.
+                    if field_attrs[index].redact.is_some() || field_attrs[index].skip {
+                        let hidden = if field_attrs[index].skip {
+                            "skipped"
+                        } else {
+                            "redacted"
+                        };
+                        return Err(error(format!(
+                            "`self.{}` is {} and can't be printed by another field",
+                            other, hidden
+                        )));
+                    }
+                    let other = &field_names[index];
+                    format.push_str(&other_arg(other).to_string());
+                    if !others.contains(other) {
+                        others.push(other.clone());
+                    }
+                } else {
+                    let index = if arg.is_empty() {
+                        next_implicit += 1;
+                        next_implicit - 1
+                    } else if let Ok(index) = arg.parse::<usize>() {
+                        format.push_str(arg);
+                        index
+                    } else {
+                        return Err(error(format!(
+                            "named argument `{}` is not supported, use `{{}}` for the field value or `{{self.{}}}` for another field",
+                            arg, arg
+                        )));
+                    };
+
+                    if index != 0 {
+                        return Err(error(format!(
+                            "the field value is the only positional argument, found a reference to argument {}",
+                            index
+                        )));
+                    }
+                    uses_value = true;
+                }
+                if !spec.is_empty() {
+                    format.push(':');
+                    format.push_str(spec);
+                }
+                format.push('}');
+            }
+            c => format.push(c),
+        }
+    }
+
+    if !uses_value {
+        return Err(error(String::from(
+            "the field value is never used, expected a `{}`",
+        )));
+    }
+
+    Ok(FieldFormat { format, others })
+}
+
+/// `{self.len}` -> `{__self_len}`, `{self.1}` -> `{__self_1}`
+fn other_arg(other: &Member) -> Ident {
+    match other {
+        Member::Named(ident) => format_ident!("__self_{}", ident),
+        Member::Unnamed(index) => format_ident!("__self_{}", index.index),
+    }
+}
+
+// format_spec := [[fill]align][sign]['#']['0'][width]['.' precision]type
+//
+// Width and precision taken from arguments (`{:1$}`, `{:.*}`, `{:width$}`)
+// are rejected since the field value is the only argument.
+fn validate_spec(spec: &str) -> Result<(), String> {
+    let chars: Vec<char> = spec.chars().collect();
+    let is_align = |c: char| matches!(c, '<' | '^' | '>');
+    let mut i = 0;
+
+    if chars.len() >= 2 && is_align(chars[1]) {
+        i = 2;
+    } else if !chars.is_empty() && is_align(chars[0]) {
+        i = 1;
+    }
+    if matches!(chars.get(i), Some('+' | '-')) {
+        i += 1;
+    }
+    if chars.get(i) == Some(&'#') {
+        i += 1;
+    }
+    if chars.get(i) == Some(&'0') && chars.get(i + 1) != Some(&'$') {
+        i += 1;
+    }
+    i = skip_count(&chars, i)?;
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        if chars.get(i) == Some(&'*') {
+            return Err(String::from(
+                "`.*` precision takes an extra argument, only the field value is available",
+            ));
+        }
+        let count_end = skip_count(&chars, i)?;
+        if count_end == i {
+            return Err(String::from("expected a precision after `.`"));
+        }
+        i = count_end;
+    }
+
+    let ty: String = chars[i..].iter().collect();
+    match ty.as_str() {
+        "" | "?" | "x?" | "X?" | "x" | "X" | "o" | "b" | "e" | "E" | "p" => Ok(()),
+        other => Err(format!("unknown format trait `{}`", other)),
+    }
+}
+
+/// Skips an integer width or precision, rejecting `1$` and `name$` parameters.
+fn skip_count(chars: &[char], start: usize) -> Result<usize, String> {
+    let mut end = start;
+    while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+        end += 1;
+    }
+
+    if chars.get(end) == Some(&'$') {
+        let parameter: String = chars[start..end].iter().collect();
+        return Err(format!(
+            "`{}$` takes the width or precision from an argument, only the field value is available",
+            parameter
+        ));
+    }
+
+    // Anything other than digits belongs to the format trait e.g. `x?`.
+    let digits = chars[start..end]
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    Ok(start + digits)
+}
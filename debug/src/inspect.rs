@@ -0,0 +1,190 @@
+use crate::attr::{get_field_attrs, get_inspect_bound, get_struct_attrs};
+use crate::bound::add_trait_bounds;
+use crate::field::{adapters, debug_fields, field_members, referenced_fields, when_printed};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Fields};
+
+// ```rust, ignore
+// #[derive(Inspect)]
+// pub struct Request<T> {
+//     method: &'static str,
+//     body: T,
+//     #[debug(redact)]
+//     token: String,
+//     #[debug = "{:#x}"]
+//     flags: u32,
+// }
+//
+// impl<T: ::inspect::InspectValue> ::inspect::Inspect for Request<T> {
+//     fn visit(&self, v: &mut dyn ::inspect::FieldVisitor) {
+//         ::inspect::InspectValue::visit_value(&self.method, "method", v);
+//         ::inspect::InspectValue::visit_value(&self.body, "body", v);
+//         v.visit_str("token", "[redacted]");
+//         v.visit_debug("flags", &format_args!("{:#x}", &self.flags));
+//     }
+// }
+// ```
+//
+// Fields read the same `#[debug(...)]` attributes as CustomDebug. Fields that
+// CustomDebug would print through a format string, `with` or `truncate` are
+// visited as their Debug output since there is no structured value to give.
+// The InspectValue bounds can be given with `#[inspect(bound = "...")]`, the
+// ones of `#[debug(bound = "...")]` only apply to fields visited through Debug.
+pub(crate) fn expand(input: DeriveInput) -> Result<TokenStream, syn::Error> {
+    let DeriveInput {
+        attrs,
+        vis: _,
+        ident,
+        generics,
+        data,
+    } = input;
+
+    let struct_attrs = get_struct_attrs(&attrs)?;
+
+    // Opaque values and values with their own formatter don't expose any field.
+    let fields = if struct_attrs.opaque || struct_attrs.with.is_some() {
+        None
+    } else {
+        match data {
+            Data::Struct(strct) => match strct.fields {
+                Fields::Named(fields) => Some(fields.named),
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "Inspect only supports structs with named fields",
+                    ))
+                }
+            },
+            Data::Enum(enm) => {
+                return Err(syn::Error::new_spanned(
+                    enm.enum_token,
+                    "Inspect only supports structs with named fields",
+                ))
+            }
+            Data::Union(union) => {
+                return Err(syn::Error::new_spanned(
+                    union.union_token,
+                    r#"Inspect cannot tell which field of a union is initialized, add `#[debug(opaque)]` or `#[debug(with = "..")]` to the union"#,
+                ))
+            }
+        }
+    };
+    let fields = fields.unwrap_or_default();
+
+    let field_attrs = fields
+        .iter()
+        .map(get_field_attrs)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let struct_inspect_bound = get_inspect_bound(&attrs)?;
+    let field_inspect_bounds = fields
+        .iter()
+        .map(|field| get_inspect_bound(&field.attrs))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let field_names = field_members(&fields);
+    let referenced = referenced_fields(&field_names, &field_attrs)?;
+
+    // Fields visited through their Debug output need Debug, the others need
+    // InspectValue. Fields printed by another one with `{self.other}` need
+    // Debug too. Fields whose bounds are given by the caller add those instead.
+    let mut inspect_types = Vec::new();
+    let mut inspect_bounds = Vec::new();
+    let mut debug_types = Vec::new();
+    let mut debug_bounds = Vec::new();
+    for (index, ((field, field_attrs), inspect_bound)) in fields
+        .iter()
+        .zip(&field_attrs)
+        .zip(&field_inspect_bounds)
+        .enumerate()
+    {
+        if referenced.contains(&index) && field_attrs.bound.is_none() {
+            debug_types.push(&field.ty);
+        }
+        if field_attrs.skip || field_attrs.redact.is_some() || field_attrs.with.is_some() {
+            continue;
+        }
+        if field_attrs.format.is_some() || field_attrs.truncate.is_some() {
+            match &field_attrs.bound {
+                Some(bound) => debug_bounds.extend(bound),
+                None => debug_types.push(&field.ty),
+            }
+        } else {
+            match inspect_bound {
+                Some(bound) => inspect_bounds.extend(bound),
+                None => inspect_types.push(&field.ty),
+            }
+        }
+    }
+
+    // `#[inspect(bound = "...")]` on the struct replaces every inferred
+    // InspectValue bound, and `#[debug(bound = "...")]` every inferred Debug
+    // bound when some field is visited through Debug.
+    let debug_bound_attr = if debug_types.is_empty() && debug_bounds.is_empty() {
+        None
+    } else {
+        struct_attrs.bound
+    };
+    let generics = add_trait_bounds(
+        generics,
+        &parse_quote!(::inspect::InspectValue),
+        inspect_types,
+        inspect_bounds,
+        struct_inspect_bound,
+    );
+    let generics = add_trait_bounds(
+        generics,
+        &parse_quote!(::core::fmt::Debug),
+        debug_types,
+        debug_bounds,
+        debug_bound_attr,
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let visits = debug_fields(&field_names, &field_attrs)?
+        .into_iter()
+        .zip(
+            field_names
+                .iter()
+                .zip(&field_attrs)
+                .filter(|(_, field_attrs)| !field_attrs.skip),
+        )
+        .map(|((label, value, skip_if), (field_name, field_attrs))| {
+            let visit = if let Some(placeholder) = &field_attrs.redact {
+                quote!(v.visit_str(#label, #placeholder);)
+            } else if field_attrs.with.is_some()
+                || field_attrs.truncate.is_some()
+                || field_attrs.format.is_some()
+            {
+                quote!(v.visit_debug(#label, &#value);)
+            } else {
+                quote!(::inspect::InspectValue::visit_value(&self.#field_name, #label, v);)
+            };
+            when_printed(&skip_if, visit)
+        })
+        .collect::<Vec<_>>();
+
+    // Without any field the visitor would be an unused variable.
+    let visitor = if visits.is_empty() {
+        quote!(_)
+    } else {
+        quote!(v)
+    };
+    let adapters = adapters(&field_attrs);
+
+    Ok(quote! {
+        impl #impl_generics ::inspect::Inspect for #ident #ty_generics #where_clause {
+            fn visit(&self, #visitor: &mut dyn ::inspect::FieldVisitor) {
+                #adapters
+                #(#visits)*
+            }
+        }
+
+        impl #impl_generics ::inspect::InspectValue for #ident #ty_generics #where_clause {
+            fn visit_value(&self, name: &str, v: &mut dyn ::inspect::FieldVisitor) {
+                v.visit_nested(name, self)
+            }
+        }
+    })
+}
@@ -1,10 +1,16 @@
+mod attr;
+mod bound;
+mod display;
+mod field;
+mod format;
+mod inspect;
+
+use attr::{get_field_attrs, get_struct_attrs, Style};
+use bound::add_trait_bounds;
+use field::{adapters, debug_fields, field_members, referenced_fields, when_printed};
 use proc_macro::TokenStream;
-use proc_macro2::Ident;
 use quote::quote;
-use syn::{
-    parse_macro_input, parse_quote, DeriveInput, Fields, GenericArgument, PathArguments, Type,
-    TypePath,
-};
+use syn::{parse_macro_input, parse_quote, punctuated::Punctuated, DeriveInput, Fields};
 
 #[proc_macro_derive(CustomDebug, attributes(debug))]
 pub fn derive(input: TokenStream) -> TokenStream {
@@ -16,234 +22,257 @@ pub fn derive(input: TokenStream) -> TokenStream {
         data,
     } = parse_macro_input!(input as DeriveInput);
 
-    let bound_attr = match get_bound_attr(&attrs) {
-        Ok(bound_attr) => bound_attr,
+    let struct_attrs = match get_struct_attrs(&attrs) {
+        Ok(struct_attrs) => struct_attrs,
         Err(err) => return err.into_compile_error().into(),
     };
 
-    let fields = match data {
-        syn::Data::Struct(strct) => {
-            if let Fields::Named(fields) = strct.fields {
-                fields.named
-            } else {
-                unimplemented!("CustomDebug only supports named fields")
+    // Opaque values and values with their own formatter don't print any field,
+    // so nothing is inferred from the fields.
+    if struct_attrs.opaque || struct_attrs.with.is_some() {
+        let body = match &struct_attrs.with {
+            Some(with) => quote!(#with(self, f)),
+            None => quote! {
+                f.debug_struct(::core::stringify!(#struct_ident)).finish_non_exhaustive()
+            },
+        };
+        let generics = add_trait_bounds(
+            generics,
+            &parse_quote!(::core::fmt::Debug),
+            Vec::new(),
+            Vec::new(),
+            struct_attrs.bound,
+        );
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        return proc_macro::TokenStream::from(quote! {
+            impl #impl_generics ::core::fmt::Debug for #struct_ident #ty_generics #where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #body
+                }
             }
+        });
+    }
+
+    // Tuple structs print like `UserId(42)` unless told otherwise.
+    let (fields, default_style) = match data {
+        syn::Data::Struct(strct) => match strct.fields {
+            Fields::Named(fields) => (fields.named, Style::Struct),
+            Fields::Unnamed(fields) => (fields.unnamed, Style::Tuple),
+            Fields::Unit => (Punctuated::new(), Style::Struct),
+        },
+        // Only one field of a union is initialized and nothing tells which one,
+        // so reading any of them to print it could be undefined behavior.
+        syn::Data::Union(union) => {
+            return syn::Error::new_spanned(
+                union.union_token,
+                r#"CustomDebug cannot tell which field of a union is initialized, add `#[debug(opaque)]` or `#[debug(with = "..")]` to the union"#,
+            )
+            .into_compile_error()
+            .into()
+        }
+        syn::Data::Enum(enm) => {
+            return syn::Error::new_spanned(
+                enm.enum_token,
+                "CustomDebug only supports structs and unions",
+            )
+            .into_compile_error()
+            .into()
         }
-        other => unimplemented!("CustomDebug is not supported for {:?}", other),
     };
 
-    let generic_idents = generics
-        .type_params()
-        .map(|t| t.ident.clone())
-        .collect::<Vec<_>>();
+    let field_attrs = match fields
+        .iter()
+        .map(get_field_attrs)
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(field_attrs) => field_attrs,
+        Err(err) => return err.into_compile_error().into(),
+    };
 
-    let phantom_types: Vec<&Ident> = fields
+    let field_names = field_members(&fields);
+    let referenced = match referenced_fields(&field_names, &field_attrs) {
+        Ok(referenced) => referenced,
+        Err(err) => return err.into_compile_error().into(),
+    };
+
+    // Skipped and redacted fields are never formatted, and fields with a custom
+    // formatter don't go through Debug, so their types must not contribute to
+    // the inferred bounds, unless another field prints them with
+    // `{self.other}`. Neither do fields whose bounds are given by the caller.
+    let field_types = fields
         .iter()
-        .filter_map(|field| get_phantom_type_ident(&field.ty, &generic_idents))
+        .zip(&field_attrs)
+        .enumerate()
+        .filter(|(index, (_, field_attrs))| {
+            (!field_attrs.skip && field_attrs.redact.is_none() && field_attrs.with.is_none()
+                || referenced.contains(index))
+                && field_attrs.bound.is_none()
+        })
+        .map(|(_, (field, _))| &field.ty)
         .collect::<Vec<_>>();
 
-    let associated_types: Vec<&TypePath> = fields
+    let field_bounds: Vec<&syn::WherePredicate> = field_attrs
         .iter()
-        .filter_map(|field| get_associated_field_type(&field.ty, &generic_idents))
+        .filter(|field_attrs| !field_attrs.skip)
+        .filter_map(|field_attrs| field_attrs.bound.as_ref())
+        .flatten()
         .collect::<Vec<_>>();
 
-    let generics = add_trait_bounds(generics, phantom_types, associated_types, bound_attr);
+    let generics = add_trait_bounds(
+        generics,
+        &parse_quote!(::core::fmt::Debug),
+        field_types,
+        field_bounds,
+        struct_attrs.bound,
+    );
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let field_names = fields.iter().map(|field| {
-        field
-            .ident
-            .as_ref()
-            .expect("Named fields should have an ident")
-    });
-
-    let field_formats = fields
-        .iter()
-        .map(|field| get_debug_attr(field).unwrap_or_else(|| String::from("{:?}")));
-
-    let output = quote! {
-        impl #impl_generics ::std::fmt::Debug for #struct_ident #ty_generics #where_clause {
-          fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
-              f.debug_struct(stringify!(#struct_ident))
-                  #(.field(stringify!(#field_names), &format_args!(#field_formats, &self.#field_names)))*
-                  .finish()
-          }
-        }
+    let debug_fields = match debug_fields(&field_names, &field_attrs) {
+        Ok(debug_fields) => debug_fields,
+        Err(err) => return err.into_compile_error().into(),
     };
+    let adapters = adapters(&field_attrs);
 
-    // eprintln!("{}", output);
-
-    proc_macro::TokenStream::from(output)
-}
+    let finish = if struct_attrs.finish_non_exhaustive {
+        quote!(finish_non_exhaustive)
+    } else {
+        quote!(finish)
+    };
 
-fn get_debug_attr(field: &syn::Field) -> Option<String> {
-    let attr = &field.attrs.get(0)?;
-    match attr.parse_meta() {
-        Ok(syn::Meta::NameValue(name_value)) => {
-            if !name_value.path.is_ident("debug") {
-                return None;
+    let body = if struct_attrs.transparent {
+        // struct UserId(u64) -> 42
+        match debug_fields.as_slice() {
+            [(_, value, None)] => quote!(::core::fmt::Debug::fmt(&#value, f)),
+            [(_, _, Some(_))] => {
+                let skip_if = field_attrs
+                    .iter()
+                    .find_map(|field_attrs| field_attrs.skip_if.as_ref());
+                return syn::Error::new_spanned(
+                    skip_if,
+                    "`skip_if` cannot be used on the field of a `debug(transparent)` struct",
+                )
+                .into_compile_error()
+                .into();
             }
-            match &name_value.lit {
-                syn::Lit::Str(lit_str) => Some(lit_str.value()),
-                _ => unimplemented!(r#"Only #[debug = ""] is supported"#),
+            _ => {
+                return syn::Error::new_spanned(
+                    &struct_ident,
+                    "`debug(transparent)` requires exactly one field that is not skipped",
+                )
+                .into_compile_error()
+                .into()
             }
         }
-        _ => None,
-    }
-}
-
-fn get_bound_attr(attrs: &[syn::Attribute]) -> Result<Option<syn::WherePredicate>, syn::Error> {
-    fn attr_error<T: quote::ToTokens>(tokens: T) -> syn::Error {
-        syn::Error::new_spanned(tokens, r#"expected `debug(bound = "..")`"#)
-    }
-
-    match attrs.get(0) {
-        Some(attr) => match attr.parse_meta() {
-            Ok(syn::Meta::List(meta_list)) => {
-                if !meta_list.path.is_ident("debug") {
-                    return Ok(None);
+    } else {
+        match struct_attrs.style.unwrap_or(default_style) {
+            Style::Struct => {
+                let fields = debug_fields.iter().map(|debug_field| {
+                    let (label, value, _) = debug_field;
+                    when_printed(&debug_field.2, quote!(__debug.field(#label, &#value);))
+                });
+                quote! {
+                    let mut __debug = f.debug_struct(::core::stringify!(#struct_ident));
+                    #(#fields)*
+                    __debug.#finish()
                 }
-
-                if meta_list.nested.len() != 1 {
-                    return Err(attr_error(meta_list.nested));
+            }
+            Style::Tuple => {
+                let fields = debug_fields.iter().map(|debug_field| {
+                    let (_, value, _) = debug_field;
+                    when_printed(&debug_field.2, quote!(__debug.field(&#value);))
+                });
+                quote! {
+                    let mut __debug = f.debug_tuple(::core::stringify!(#struct_ident));
+                    #(#fields)*
+                    __debug.#finish()
                 }
-
-                match &meta_list.nested[0] {
-                    syn::NestedMeta::Meta(syn::Meta::NameValue(name_value)) => {
-                        if !name_value.path.is_ident("bound") {
-                            return Err(attr_error(&name_value.path));
-                        }
-
-                        match &name_value.lit {
-                            syn::Lit::Str(lit_str) => {
-                                let bound_str = lit_str.value();
-                                match syn::parse_str::<syn::WherePredicate>(&bound_str) {
-                                    Ok(where_clause) => Ok(Some(where_clause)),
-                                    Err(err) => Err(syn::Error::new_spanned(&name_value.lit, err)),
-                                }
-                            }
-                            _ => Err(attr_error(&name_value.lit)),
-                        }
+            }
+            // Each value is written through a fresh `{:?}`, so neither the
+            // struct nor the values it contains are affected by `{:#?}`.
+            Style::Flat => {
+                let (end_empty, end) = if struct_attrs.finish_non_exhaustive {
+                    (" { .. }", ", .. }")
+                } else {
+                    ("", " }")
+                };
+                if debug_fields.is_empty() {
+                    quote! {
+                        f.write_str(::core::stringify!(#struct_ident))?;
+                        f.write_str(#end_empty)
+                    }
+                } else {
+                    // Whether a field has been written yet is only known at
+                    // runtime once some of them can be skipped.
+                    let fields = debug_fields.iter().map(|debug_field| {
+                        let (label, value, _) = debug_field;
+                        when_printed(
+                            &debug_field.2,
+                            quote! {
+                                let __separator = if __empty { " { " } else { ", " };
+                                ::core::write!(f, "{}{}: {:?}", __separator, #label, &#value)?;
+                                __empty = false;
+                            },
+                        )
+                    });
+                    quote! {
+                        f.write_str(::core::stringify!(#struct_ident))?;
+                        let mut __empty = true;
+                        #(#fields)*
+                        f.write_str(if __empty { #end_empty } else { #end })
                     }
-                    _ => Err(attr_error(&meta_list.nested[0])),
                 }
             }
-            _ => Ok(None),
-        },
-
-        None => Ok(None),
-    }
-}
-
-fn add_trait_bounds(
-    mut generics: syn::Generics,
-    phantom_types: Vec<&Ident>,
-    associated_types: Vec<&TypePath>,
-    bound_attr: Option<syn::WherePredicate>,
-) -> syn::Generics {
-    if let Some(where_clause) = bound_attr {
-        generics
-            .make_where_clause()
-            .predicates
-            .push(parse_quote!(#where_clause));
-    } else {
-        let associated_types_ident: Vec<&Ident> = associated_types
-            .iter()
-            .map(|type_path| &type_path.path.segments[0].ident)
-            .collect();
-
-        // impl <T: Debug, T2: Debug> Debug for Foo { ... }
-        for type_param in generics.type_params_mut() {
-            // Skip bound for phantom types.
-            if phantom_types.contains(&&type_param.ident) {
-                continue;
-            }
-
-            // Skip bound for associated types.
-            if associated_types_ident.contains(&&type_param.ident) {
-                continue;
+            Style::Compact => {
+                let end = if struct_attrs.finish_non_exhaustive {
+                    " .."
+                } else {
+                    ""
+                };
+                let fields = debug_fields.iter().map(|debug_field| {
+                    let (label, value, _) = debug_field;
+                    when_printed(
+                        &debug_field.2,
+                        quote!(::core::write!(f, " {}={:?}", #label, &#value)?;),
+                    )
+                });
+                quote! {
+                    f.write_str(::core::stringify!(#struct_ident))?;
+                    #(#fields)*
+                    f.write_str(#end)
+                }
             }
-
-            type_param.bounds.push(parse_quote!(::std::fmt::Debug));
         }
+    };
 
-        // impl <T: Debug, T2: Debug> Debug for Foo { ... } where <T3::Value>: Debug
-        let where_clause = generics.make_where_clause();
-        for associated_type in associated_types {
-            // Notice, here we need to pass the whole type and not only the ident.
-            where_clause.predicates.push(parse_quote! {
-                #associated_type : ::std::fmt::Debug
-            })
+    let output = quote! {
+        impl #impl_generics ::core::fmt::Debug for #struct_ident #ty_generics #where_clause {
+          fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+              #adapters
+              #body
+          }
         }
-    }
-    generics
-}
+    };
 
-/// Retrieves the type parameter `T` of a phantom type `Phantom<T>`.
-fn get_phantom_type_ident<'a>(ty: &'a Type, generic_idents: &[Ident]) -> Option<&'a syn::Ident> {
-    if let syn::Type::Path(type_path) = inner_type(ty, Some("PhantomData"))? {
-        let type_ident = &type_path.path.segments.first()?.ident;
-        if generic_idents.contains(type_ident) {
-            return Some(type_ident);
-        }
-    }
-    None
-}
+    // eprintln!("{}", output);
 
-/// Retrieves the inner most associated type e.g. `Box<Option<Vec<T::Value>> -> T::Value`.
-fn get_associated_field_type<'a>(
-    ty: &'a Type,
-    generic_idents: &[Ident],
-) -> Option<&'a syn::TypePath> {
-    let mut ty: &Type = ty;
-    while let Some(inner_type) = inner_type(ty, None) {
-        ty = inner_type;
-    }
+    proc_macro::TokenStream::from(output)
+}
 
-    if let Type::Path(type_path @ TypePath { qself: None, path }) = ty {
-        if path.segments.len() > 1 {
-            let type_ident = &type_path.path.segments.first()?.ident;
-            if generic_idents.contains(type_ident) {
-                return Some(type_path);
-            }
-        }
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match display::expand(input) {
+        Ok(output) => output.into(),
+        Err(err) => err.into_compile_error().into(),
     }
-    None
 }
 
-/// Returns the type parameter of a type constructor e.g. `PhantomData<T> -> T`.
-fn inner_type<'a>(
-    ty: &'a Type,
-    wrapping_ty_ident: Option<&str>, /* None = ignore */
-) -> Option<&'a syn::Type> {
-    // Tip: eprintln! on the Type.
-    // NB. Make sure the test compiles to be able to emit the debug to the sdterr.
-    // eprintln!("{ty:#?}");
-
-    if let Type::Path(syn::TypePath {
-        qself: None,
-        ref path,
-    }) = ty
-    {
-        if path.segments.len() != 1 {
-            return None;
-        }
-
-        if let Some(ty_ident) = wrapping_ty_ident {
-            if path.segments[0].ident != ty_ident {
-                return None;
-            }
-        }
-
-        if let PathArguments::AngleBracketed(ref inner_type) = path.segments[0].arguments {
-            if inner_type.args.len() != 1 {
-                return None;
-            }
-
-            if let GenericArgument::Type(ref ty) = inner_type.args[0] {
-                return Some(ty);
-            }
-        }
+#[proc_macro_derive(Inspect, attributes(debug, inspect))]
+pub fn derive_inspect(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match inspect::expand(input) {
+        Ok(output) => output.into(),
+        Err(err) => err.into_compile_error().into(),
     }
-    None
 }
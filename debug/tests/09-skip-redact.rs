@@ -0,0 +1,60 @@
+// Structs that carry secrets should not leak them through Debug. A field can be
+// omitted from the output with #[debug(skip)], or replaced by a placeholder
+// with #[debug(redact)] or #[debug(redact = "...")].
+//
+// Since the value of a skipped or redacted field is never formatted, its type
+// should not contribute a `T: Debug` bound to the generated impl:
+//
+//     impl<T, U: Debug> Debug for Credentials<T, U> {...}
+//
+// With the struct-level #[debug(finish_non_exhaustive)] the output ends in
+// `..` to make it obvious that some fields were left out.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+#[derive(CustomDebug)]
+pub struct Login {
+    user: &'static str,
+    #[debug(redact)]
+    password: &'static str,
+    #[debug(redact = "***")]
+    token: u64,
+}
+
+#[derive(CustomDebug)]
+#[debug(finish_non_exhaustive)]
+pub struct Credentials<T, U> {
+    #[debug(skip)]
+    secret: T,
+    #[debug(redact)]
+    key: Option<T>,
+    public: U,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    // Does not implement Debug.
+    struct NotDebug;
+
+    let login = Login {
+        user: "root",
+        password: "hunter2",
+        token: 42,
+    };
+    let debug = format!("{:?}", login);
+    let expected = r#"Login { user: "root", password: [redacted], token: *** }"#;
+    assert_eq!(debug, expected);
+
+    let credentials = Credentials {
+        secret: NotDebug,
+        key: None,
+        public: 1,
+    };
+    let debug = format!("{:?}", credentials);
+    let expected = r#"Credentials { key: [redacted], public: 1, .. }"#;
+    assert_eq!(debug, expected);
+
+    assert_debug::<Credentials<NotDebug, u8>>();
+}
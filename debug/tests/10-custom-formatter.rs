@@ -0,0 +1,64 @@
+// Some field types don't implement Debug at all, like foreign handles or raw
+// pointers, and others should be rendered in a domain-specific way. For those
+// the caller can name a function with the signature
+//
+//     fn(&T, &mut fmt::Formatter) -> fmt::Result
+//
+// in #[debug(with = "path::to::fmt_fn")] and the macro will route the field
+// through it. Since such a field never goes through Debug, its type should not
+// contribute a `T: Debug` bound to the generated impl.
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+use std::time::Duration;
+
+mod fmt_utils {
+    use std::fmt;
+    use std::time::Duration;
+
+    pub fn millis(duration: &Duration, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}ms", duration.as_millis())
+    }
+
+    pub fn hex<B: AsRef<[u8]>>(bytes: &B, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in bytes.as_ref() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Handle(u32);
+
+fn opaque<T>(_: &T, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("<opaque>")
+}
+
+#[derive(CustomDebug)]
+pub struct Connection<H> {
+    #[debug(with = "fmt_utils::millis")]
+    timeout: Duration,
+    #[debug(with = "fmt_utils::hex")]
+    key: [u8; 4],
+    #[debug(with = "opaque")]
+    handle: H,
+    raw: u8,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    let connection = Connection {
+        timeout: Duration::from_secs(2),
+        key: [0xde, 0xad, 0xbe, 0xef],
+        handle: Handle(3),
+        raw: 7,
+    };
+
+    let debug = format!("{:?}", connection);
+    let expected = r#"Connection { timeout: 2000ms, key: deadbeef, handle: <opaque>, raw: 7 }"#;
+    assert_eq!(debug, expected);
+
+    assert_debug::<Connection<Handle>>();
+    assert_eq!(connection.handle.0, 3);
+}
@@ -0,0 +1,46 @@
+// The `debug` attributes don't have to come first. Any number of unrelated
+// attributes like #[doc] or #[allow] may be placed around them, and several
+// `debug` attributes on the same item are merged together.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+}
+
+/// Documented wrapper.
+#[derive(CustomDebug)]
+#[allow(dead_code)]
+#[debug(bound = "T::Value: Debug")]
+#[debug(finish_non_exhaustive)]
+pub struct Wrapper<T: Trait> {
+    /// The values.
+    #[allow(clippy::all)]
+    #[debug = "{:x?}"]
+    values: Vec<T::Value>,
+    #[doc = "Never shown."]
+    #[debug(skip)]
+    marker: T,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    let wrapper = Wrapper {
+        values: vec![10, 11],
+        marker: Id,
+    };
+
+    let debug = format!("{:?}", wrapper);
+    let expected = r#"Wrapper { values: [a, b], .. }"#;
+    assert_eq!(debug, expected);
+
+    assert_debug::<Wrapper<Id>>();
+}
@@ -0,0 +1,27 @@
+// Giving the same option twice, or two options that each decide how a field
+// is printed, is most likely a mistake. Point at the offending attribute.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Login {
+    #[debug(redact)]
+    #[debug(redact = "***")]
+    password: &'static str,
+}
+
+#[derive(CustomDebug)]
+pub struct Field {
+    #[debug = "0b{:08b}"]
+    #[debug(skip)]
+    bitmask: u8,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T: Clone")]
+#[debug(bound = "T: Copy")]
+pub struct Wrapper<T> {
+    value: T,
+}
+
+fn main() {}
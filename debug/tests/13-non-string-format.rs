@@ -0,0 +1,11 @@
+// The format given in #[debug = "..."] has to be a string literal.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Field {
+    #[debug = 5]
+    bitmask: u8,
+}
+
+fn main() {}
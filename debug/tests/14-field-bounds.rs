@@ -0,0 +1,69 @@
+// The escape hatch from test 08 accepts any number of comma separated where
+// predicates. An empty `bound = ""` disables inference altogether, which is
+// useful when a type parameter only appears in places that don't need Debug.
+//
+// The same attribute can be placed on an individual field. It then substitutes
+// only whatever bounds would have been inferred from that field's type,
+// without removing bounds inferred from the other fields:
+//
+//     #[derive(CustomDebug)]
+//     pub struct Wrapper<T: Trait, U> {
+//         #[debug(bound = "T::Value: Debug")]
+//         field: Field<T>,
+//         normal: U,
+//     }
+//
+// expands to
+//
+//     impl<T: Trait, U: Debug> Debug for Wrapper<T, U>
+//     where
+//         T::Value: Debug,
+//     {...}
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T: Trait, U> {
+    #[debug(bound = "T::Value: Debug")]
+    field: Field<T>,
+    normal: U,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T::Value: Debug, U::Value: Debug,")]
+pub struct Pair<T: Trait, U: Trait> {
+    left: Field<T>,
+    right: Field<U>,
+}
+
+// Debug for PhantomData<fn() -> T> holds for every T.
+#[derive(CustomDebug)]
+#[debug(bound = "")]
+pub struct Unbounded<T> {
+    marker: PhantomData<fn() -> T>,
+}
+
+#[derive(CustomDebug)]
+struct Field<T: Trait> {
+    values: Vec<T::Value>,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Wrapper<Id, u8>>();
+    assert_debug::<Pair<Id, Id>>();
+    assert_debug::<Unbounded<Id>>();
+}
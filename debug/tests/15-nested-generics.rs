@@ -0,0 +1,91 @@
+// Bound inference walks the whole type of every field rather than only looking
+// through single-argument wrappers. Each type parameter ends up bounded only
+// if some field actually needs it to implement Debug:
+//
+//   - HashMap<K, V::Assoc>     ->  K: Debug, V::Assoc: Debug
+//   - &'a T                    ->  T: Debug
+//   - [T; N]                   ->  T: Debug
+//   - (T, U::Assoc)            ->  T: Debug, U::Assoc: Debug
+//   - <T as Trait>::Assoc      ->  <T as Trait>::Assoc: Debug
+//   - Box<dyn Shape<T>>        ->  nothing, the trait object is Debug on its own
+//   - std::marker::PhantomData ->  nothing
+//   - fn(T) -> U               ->  nothing, function pointers are always Debug
+//   - *const T                 ->  nothing, raw pointers are always Debug
+
+use derive_debug::CustomDebug;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Assoc;
+}
+
+pub trait Shape<T>: Debug {}
+
+#[derive(CustomDebug)]
+pub struct Map<K, V: Trait> {
+    map: HashMap<K, V::Assoc>,
+}
+
+#[derive(CustomDebug)]
+pub struct Ref<'a, T> {
+    value: &'a T,
+}
+
+#[derive(CustomDebug)]
+pub struct Array<T, const N: usize> {
+    values: [T; N],
+}
+
+#[derive(CustomDebug)]
+pub struct Tuple<T, U: Trait> {
+    pair: (T, U::Assoc),
+}
+
+#[derive(CustomDebug)]
+pub struct Qualified<T: Trait> {
+    value: <T as Trait>::Assoc,
+}
+
+#[derive(CustomDebug)]
+pub struct Boxed<T> {
+    shape: Box<dyn Shape<T>>,
+}
+
+#[derive(CustomDebug)]
+pub struct Marker<T> {
+    marker: std::marker::PhantomData<T>,
+}
+
+#[derive(CustomDebug)]
+pub struct Callback<T, U> {
+    callback: fn(T) -> U,
+    raw: *const T,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    // Does not implement Debug.
+    struct NotDebug;
+
+    // Does not implement Debug, but its associated type does.
+    struct Id;
+
+    impl Trait for Id {
+        type Assoc = u8;
+    }
+
+    assert_debug::<Map<u8, Id>>();
+    assert_debug::<Ref<u8>>();
+    assert_debug::<Array<u8, 4>>();
+    assert_debug::<Tuple<u8, Id>>();
+    assert_debug::<Qualified<Id>>();
+    assert_debug::<Boxed<NotDebug>>();
+    assert_debug::<Marker<NotDebug>>();
+    assert_debug::<Callback<NotDebug, NotDebug>>();
+
+    let value = 5;
+    let debug = format!("{:?}", Ref { value: &value });
+    assert_eq!(debug, "Ref { value: 5 }");
+}
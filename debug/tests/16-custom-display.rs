@@ -0,0 +1,85 @@
+// The CustomDisplay derive generates std::fmt::Display from a template given
+// in #[display("...")] on a struct, or on each variant of an enum. Fields are
+// referenced by name, or by index for tuple fields, and accept the usual
+// format specs:
+//
+//     #[derive(CustomDisplay)]
+//     #[display("conn {host}:{port} failed")]
+//     pub struct ConnError {
+//         host: String,
+//         port: u16,
+//     }
+//
+// Unit variants without a template display their own name, and an enum
+// without variants can't be displayed at all. Display bounds on
+// the type parameters are inferred from the referenced fields, the same way as
+// the Debug bounds of CustomDebug.
+
+use derive_debug::{CustomDebug, CustomDisplay};
+use std::fmt::Display;
+
+#[derive(CustomDebug, CustomDisplay)]
+#[display("conn {host}:{port} failed")]
+pub struct ConnError {
+    host: String,
+    port: u16,
+}
+
+#[derive(CustomDisplay)]
+#[display("{0} at {1:>4}")]
+pub struct Position<T>(T, u32);
+
+#[derive(CustomDisplay)]
+pub enum Error<E> {
+    #[display("io error: {0}")]
+    Io(E),
+    #[display("{{invalid}} {line:02}:{column:02}")]
+    Parse { line: usize, column: usize },
+    Eof,
+}
+
+#[derive(CustomDisplay)]
+pub enum Value {
+    #[display("value {f}")]
+    Float { f: u8 },
+}
+
+#[derive(CustomDisplay)]
+pub enum Never {}
+
+#[derive(CustomDisplay)]
+#[display("just {value}")]
+pub struct Just<T, U> {
+    value: T,
+    #[allow(dead_code)]
+    unused: U,
+}
+
+fn assert_display<F: Display>() {}
+
+fn main() {
+    // Does not implement Display.
+    struct NotDisplay;
+
+    let conn = ConnError {
+        host: String::from("localhost"),
+        port: 8080,
+    };
+    assert_eq!(conn.to_string(), "conn localhost:8080 failed");
+    assert_eq!(
+        format!("{:?}", conn),
+        r#"ConnError { host: "localhost", port: 8080 }"#,
+    );
+
+    assert_eq!(Position("here", 7).to_string(), "here at    7");
+
+    assert_eq!(Error::Io("broken pipe").to_string(), "io error: broken pipe");
+    let parse: Error<u8> = Error::Parse { line: 3, column: 9 };
+    assert_eq!(parse.to_string(), "{invalid} 03:09");
+    assert_eq!(Error::<u8>::Eof.to_string(), "Eof");
+
+    assert_eq!(Value::Float { f: 2 }.to_string(), "value 2");
+    assert_display::<Never>();
+
+    assert_display::<Just<u8, NotDisplay>>();
+}
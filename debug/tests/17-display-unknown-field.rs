@@ -0,0 +1,20 @@
+// Every placeholder in a display template has to name a field of the struct or
+// variant. Typos are reported at compile time on the template itself rather
+// than as an error from inside the generated write! call.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+#[display("conn {hots}:{port} failed")]
+pub struct ConnError {
+    host: String,
+    port: u16,
+}
+
+#[derive(CustomDisplay)]
+pub enum Error {
+    #[display("io error: {1}")]
+    Io(String),
+}
+
+fn main() {}
@@ -0,0 +1,47 @@
+// The format string of #[debug = "..."] is validated by the macro. The field
+// value is its only positional argument and may be used any number of times.
+// Other fields of the struct can be referenced as {self.other}, which bounds
+// their type by Debug even if they are printed with their own formatter.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Field {
+    #[debug = "{0:#x} ({0})"]
+    bitmask: u8,
+    #[debug = "{:.1}{self.unit}"]
+    value: f32,
+    #[debug = "{:>3?}"]
+    unit: &'static str,
+    #[debug = "{{{:+}}}"]
+    offset: i8,
+}
+
+#[derive(CustomDebug)]
+pub struct Gen<T> {
+    #[debug = "{} / {self.t:?}"]
+    a: u8,
+    #[debug(with = "fmt_hidden")]
+    t: T,
+}
+
+fn fmt_hidden<T>(_: &T, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    f.write_str("..")
+}
+
+fn main() {
+    let f = Field {
+        bitmask: 0b00011100,
+        value: 2.25,
+        unit: "ms",
+        offset: 3,
+    };
+
+    let debug = format!("{:?}", f);
+    let expected = r#"Field { bitmask: 0x1c (28), value: 2.2ms, unit: "ms", offset: {+3} }"#;
+
+    assert_eq!(debug, expected);
+
+    let gen = Gen { a: 1, t: "x" };
+    assert_eq!(format!("{:?}", gen), r#"Gen { a: 1 / "x", t: .. }"#);
+}
@@ -0,0 +1,37 @@
+// Mistakes in a #[debug = "..."] format string are reported on the attribute
+// itself instead of as a confusing format_args! error from inside the macro
+// expansion.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct UnknownTrait {
+    #[debug = "0b{:08q}"]
+    bitmask: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct TooManyArguments {
+    #[debug = "{} and {}"]
+    bitmask: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct NamedArgument {
+    #[debug = "{bits:b}"]
+    bitmask: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct UnknownField {
+    #[debug = "{} of {self.total}"]
+    bitmask: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct Unused {
+    #[debug = "hidden"]
+    bitmask: u8,
+}
+
+fn main() {}
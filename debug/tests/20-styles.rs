@@ -0,0 +1,105 @@
+// The struct-level #[debug(style = "...")] attribute changes how the fields
+// are laid out:
+//
+//   - "struct"   Point { x: 1, y: 2 }, the default
+//   - "flat"     Point { x: 1, y: 2 }, on a single line even with {:#?}
+//   - "compact"  Point x=1 y=2, on a single line even with {:#?}
+//   - "tuple"    Point(1, 2)
+//
+// A tuple struct defaults to "tuple", its fields being labeled by their index
+// in the other styles. #[debug(rename = "...")] changes the label printed for
+// a field, and #[debug(transparent)] makes a newtype print exactly like its
+// only field.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(style = "flat")]
+pub struct Flat {
+    #[debug(rename = "id")]
+    user_id: u64,
+    roles: Vec<&'static str>,
+}
+
+#[derive(CustomDebug)]
+#[debug(style = "compact", finish_non_exhaustive)]
+pub struct Compact {
+    #[debug(rename = "id")]
+    user_id: u64,
+    name: &'static str,
+    #[debug(skip)]
+    password: &'static str,
+}
+
+#[derive(CustomDebug)]
+#[debug(style = "tuple")]
+pub struct Tuple {
+    x: i32,
+    #[debug = "{:+}"]
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+pub struct Pair(i32, #[debug = "{} of {self.0}"] i32);
+
+#[derive(CustomDebug)]
+#[debug(style = "compact")]
+pub struct CompactPair(i32, #[debug(rename = "second")] i32);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct UserId(u64);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct GroupId {
+    id: u64,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Secret {
+    #[debug(redact)]
+    value: &'static str,
+    #[debug(skip)]
+    salt: u32,
+}
+
+fn main() {
+    let flat = Flat {
+        user_id: 7,
+        roles: vec!["admin", "dev"],
+    };
+    let expected = r#"Flat { id: 7, roles: ["admin", "dev"] }"#;
+    assert_eq!(format!("{:?}", flat), expected);
+    assert_eq!(format!("{:#?}", flat), expected);
+
+    let compact = Compact {
+        user_id: 7,
+        name: "root",
+        password: "hunter2",
+    };
+    let expected = r#"Compact id=7 name="root" .."#;
+    assert_eq!(format!("{:?}", compact), expected);
+    assert_eq!(format!("{:#?}", compact), expected);
+    assert_eq!(compact.password, "hunter2");
+
+    let tuple = Tuple { x: 1, y: 2 };
+    assert_eq!(format!("{:?}", tuple), "Tuple(1, +2)");
+    assert_eq!(format!("{:#?}", tuple), "Tuple(\n    1,\n    +2,\n)");
+
+    assert_eq!(format!("{:?}", Pair(1, 2)), "Pair(1, 2 of 1)");
+    assert_eq!(format!("{:?}", CompactPair(1, 2)), "CompactPair 0=1 second=2");
+
+    let id = UserId(42);
+    assert_eq!(format!("{:?}", id), "42");
+    assert_eq!(format!("{:#x?}", id), "0x2a");
+    assert_eq!(format!("{:?}", GroupId { id: 7 }), "7");
+
+    let secret = Secret {
+        value: "hunter2",
+        salt: 3,
+    };
+    assert_eq!(format!("{:?}", secret), "[redacted]");
+    assert_eq!(secret.salt, 3);
+}
@@ -0,0 +1,88 @@
+// The generated impls only refer to `::core`, so both derives keep working in
+// a `#![no_std]` crate where a `::std::fmt` path would fail to resolve.
+//
+// The standard library is still linked under another name to provide the
+// panic handler and the entry point of this test binary, but it is not
+// reachable as `::std`.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std as _std;
+
+use alloc::format;
+use core::fmt::{self, Debug};
+use core::marker::PhantomData;
+use derive_debug::{CustomDebug, CustomDisplay};
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub struct Field<T: Trait, U> {
+    value: T::Value,
+    #[debug = "0b{:08b}"]
+    bitmask: u8,
+    other: U,
+    #[debug(skip)]
+    marker: PhantomData<T>,
+}
+
+#[derive(CustomDebug)]
+#[debug(style = "compact", finish_non_exhaustive)]
+pub struct Login {
+    #[debug(rename = "user")]
+    name: &'static str,
+    #[debug(redact)]
+    password: &'static str,
+    #[debug(with = "fmt_millis")]
+    timeout: u64,
+}
+
+fn fmt_millis(millis: &u64, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}ms", millis)
+}
+
+#[derive(CustomDisplay)]
+pub enum Event<T> {
+    #[display("moved to {0}")]
+    Moved(T),
+    Stopped,
+}
+
+struct Id;
+
+impl Trait for Id {
+    type Value = u16;
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    assert_debug::<Field<Id, bool>>();
+
+    let field = Field::<Id, bool> {
+        value: 42,
+        bitmask: 0b1011,
+        other: true,
+        marker: PhantomData,
+    };
+    assert_eq!(
+        format!("{:?}", field),
+        "Field { value: 42, bitmask: 0b00001011, other: true }",
+    );
+
+    let login = Login {
+        name: "ferris",
+        password: "hunter2",
+        timeout: 1500,
+    };
+    assert_eq!(
+        format!("{:?}", login),
+        r#"Login user="ferris" password=[redacted] timeout=1500ms .."#,
+    );
+
+    assert_eq!(format!("{}", Event::Moved(3)), "moved to 3");
+    assert_eq!(format!("{}", Event::<u8>::Stopped), "Stopped");
+}
@@ -0,0 +1,64 @@
+// #[debug(skip_if = "...")] leaves a field out of the output whenever the given
+// predicate returns true for it. The predicate is called with a reference to
+// the field, so methods such as `Option::is_none` or `Vec::is_empty` can be
+// used directly.
+//
+// #[debug(truncate = N)] prints only the first N elements of a field that can
+// be iterated by reference, followed by how many were left out:
+//
+//     Batch { samples: [1, 2, 3, ... (7 more)] }
+
+use derive_debug::CustomDebug;
+use std::collections::BTreeMap;
+
+#[derive(CustomDebug)]
+pub struct Batch<T> {
+    id: u32,
+    #[debug(skip_if = "Option::is_none")]
+    parent: Option<u32>,
+    #[debug(truncate = 3)]
+    samples: Vec<T>,
+    #[debug(truncate = 1, skip_if = "BTreeMap::is_empty")]
+    tags: BTreeMap<&'static str, u8>,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+#[derive(CustomDebug)]
+#[debug(style = "flat")]
+pub struct Flat {
+    #[debug(skip_if = "is_zero")]
+    x: u32,
+    #[debug(skip_if = "is_zero")]
+    y: u32,
+}
+
+fn main() {
+    let batch = Batch {
+        id: 1,
+        parent: None,
+        samples: (1..=10).collect(),
+        tags: BTreeMap::new(),
+    };
+    assert_eq!(
+        format!("{:?}", batch),
+        "Batch { id: 1, samples: [1, 2, 3, ... (7 more)] }",
+    );
+
+    let batch = Batch {
+        id: 2,
+        parent: Some(1),
+        samples: vec!["a", "b"],
+        tags: [("a", 1), ("b", 2)].into_iter().collect(),
+    };
+    assert_eq!(
+        format!("{:?}", batch),
+        r#"Batch { id: 2, parent: Some(1), samples: ["a", "b"], tags: [("a", 1), ... (1 more)] }"#,
+    );
+
+    assert_eq!(format!("{:?}", Flat { x: 0, y: 0 }), "Flat");
+    assert_eq!(format!("{:?}", Flat { x: 0, y: 2 }), "Flat { y: 2 }");
+    assert_eq!(format!("{:#?}", Flat { x: 1, y: 2 }), "Flat { x: 1, y: 2 }");
+}
@@ -0,0 +1,59 @@
+// Only one field of a union is initialized at a time and nothing in the type
+// says which one, so the derive cannot read any of them. A union has to either
+// be printed without its contents using #[debug(opaque)], or be given a
+// function that knows how to print it with #[debug(with = "...")].
+//
+// Both also work on structs, for instance for handles whose contents are
+// meaningless or must not be printed.
+
+use derive_debug::CustomDebug;
+use std::fmt;
+
+#[derive(CustomDebug)]
+#[debug(opaque)]
+pub union Value {
+    int: i64,
+    float: f64,
+}
+
+#[repr(C)]
+#[derive(CustomDebug)]
+#[debug(with = "fmt_tagged")]
+pub union Tagged {
+    tag: u8,
+    int: (u8, i32),
+}
+
+fn fmt_tagged(tagged: &Tagged, f: &mut fmt::Formatter) -> fmt::Result {
+    // The tag is always initialized, it is the first byte of every variant.
+    match unsafe { tagged.tag } {
+        0 => write!(f, "Tagged::Empty"),
+        _ => write!(f, "Tagged::Int({})", unsafe { tagged.int.1 }),
+    }
+}
+
+pub struct NotDebug;
+
+#[derive(CustomDebug)]
+#[debug(opaque)]
+pub struct Handle<T> {
+    raw: *mut T,
+    inner: NotDebug,
+}
+
+fn main() {
+    let value = Value { int: 7 };
+    assert_eq!(format!("{:?}", value), "Value { .. }");
+    assert_eq!(format!("{:#?}", value), "Value { .. }");
+
+    let tagged = Tagged { int: (1, -5) };
+    assert_eq!(format!("{:?}", tagged), "Tagged::Int(-5)");
+    let tagged = Tagged { tag: 0 };
+    assert_eq!(format!("{:?}", tagged), "Tagged::Empty");
+
+    let handle = Handle::<NotDebug> {
+        raw: std::ptr::null_mut(),
+        inner: NotDebug,
+    };
+    assert_eq!(format!("{:?}", handle), "Handle { .. }");
+}
@@ -0,0 +1,18 @@
+// A union without #[debug(opaque)] or #[debug(with = "...")] is rejected, since
+// printing it would mean reading a field that may not be initialized.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub union Value {
+    int: i64,
+    float: f64,
+}
+
+#[derive(CustomDebug)]
+#[debug(opaque, finish_non_exhaustive)]
+pub union Opaque {
+    int: i64,
+}
+
+fn main() {}
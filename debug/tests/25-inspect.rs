@@ -0,0 +1,100 @@
+// #[derive(Inspect)] implements the `inspect::Inspect` trait, which hands every
+// field to a visitor as a structured value instead of formatting the whole
+// struct to a string. This lets a logger emit key/value pairs directly.
+//
+// The derive reads the same #[debug(...)] attributes as CustomDebug: skipped
+// fields are not visited, redacted fields are visited as their placeholder,
+// and fields printed through a format string or a custom formatter are
+// visited as their Debug output. Type parameters are bounded by
+// `inspect::InspectValue` the same way CustomDebug bounds them by Debug.
+
+use derive_debug::CustomDebug;
+use inspect::{FieldVisitor, Inspect};
+use std::fmt::{Debug, Write};
+use std::marker::PhantomData;
+
+#[derive(CustomDebug, Inspect)]
+pub struct Request<T> {
+    method: &'static str,
+    status: u16,
+    latency: f64,
+    body: T,
+    #[debug(redact)]
+    token: String,
+    #[debug = "{:#x}"]
+    flags: u32,
+    #[debug(skip_if = "Option::is_none")]
+    parent: Option<u64>,
+    #[debug(rename = "peer")]
+    client: Client,
+    #[debug(skip)]
+    marker: PhantomData<T>,
+}
+
+#[derive(CustomDebug, Inspect)]
+pub struct Client {
+    addr: &'static str,
+    retries: Vec<u8>,
+}
+
+// Writes `name=value` pairs like a JSON-ish logger would.
+#[derive(Default)]
+struct Pairs {
+    prefix: String,
+    out: String,
+}
+
+impl Pairs {
+    fn push(&mut self, name: &str, value: impl std::fmt::Display) {
+        write!(self.out, " {}{}={}", self.prefix, name, value).unwrap();
+    }
+}
+
+impl FieldVisitor for Pairs {
+    fn visit_debug(&mut self, name: &str, value: &dyn Debug) {
+        self.push(name, format_args!("debug({:?})", value));
+    }
+
+    fn visit_u64(&mut self, name: &str, value: u64) {
+        self.push(name, value);
+    }
+
+    fn visit_f64(&mut self, name: &str, value: f64) {
+        self.push(name, value);
+    }
+
+    fn visit_str(&mut self, name: &str, value: &str) {
+        self.push(name, format_args!("{:?}", value));
+    }
+
+    fn visit_nested(&mut self, name: &str, value: &dyn Inspect) {
+        let nested_prefix = format!("{}{}.", self.prefix, name);
+        let prefix = std::mem::replace(&mut self.prefix, nested_prefix);
+        value.visit(self);
+        self.prefix = prefix;
+    }
+}
+
+fn main() {
+    let request = Request {
+        method: "GET",
+        status: 200,
+        latency: 0.25,
+        body: "ok",
+        token: String::from("hunter2"),
+        flags: 255,
+        parent: None,
+        client: Client {
+            addr: "10.0.0.1",
+            retries: vec![1, 2],
+        },
+        marker: PhantomData,
+    };
+
+    let mut pairs = Pairs::default();
+    request.visit(&mut pairs);
+    assert_eq!(
+        pairs.out,
+        r#" method="GET" status=200 latency=0.25 body="ok" token="[redacted]" flags=debug(0xff) peer.addr="10.0.0.1" peer.retries=1 peer.retries=2"#,
+    );
+}
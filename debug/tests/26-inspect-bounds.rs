@@ -0,0 +1,74 @@
+// The bounds given with #[debug(bound = "...")] are written for Debug, so the
+// Inspect derive doesn't use them: it infers InspectValue bounds for those
+// fields the same way it does for the others. When inference is not enough,
+// like for the Wrapper of test 08, #[inspect(bound = "...")] gives the
+// InspectValue bounds by hand, on the struct or on a field.
+
+use derive_debug::CustomDebug;
+use inspect::{FieldVisitor, Inspect};
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug, Inspect)]
+#[debug(bound = "T::Value: Debug")]
+pub struct Values<T: Trait> {
+    values: Vec<T::Value>,
+}
+
+#[derive(CustomDebug, Inspect)]
+#[debug(bound = "T::Value: Debug")]
+#[inspect(bound = "T::Value: inspect::InspectValue")]
+pub struct Wrapper<T: Trait> {
+    field: Values<T>,
+}
+
+#[derive(CustomDebug, Inspect)]
+pub struct Fields<T: Trait, U> {
+    #[debug(bound = "T::Value: Debug")]
+    #[inspect(bound = "T::Value: inspect::InspectValue")]
+    field: Values<T>,
+    normal: U,
+}
+
+#[derive(Default)]
+struct Names(Vec<String>);
+
+impl FieldVisitor for Names {
+    fn visit_debug(&mut self, name: &str, _: &dyn Debug) {
+        self.0.push(name.to_owned());
+    }
+
+    fn visit_u64(&mut self, name: &str, _: u64) {
+        self.0.push(name.to_owned());
+    }
+
+    fn visit_nested(&mut self, name: &str, value: &dyn Inspect) {
+        self.0.push(name.to_owned());
+        value.visit(self);
+    }
+}
+
+fn assert_inspect<I: Inspect>() {}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_inspect::<Values<Id>>();
+    assert_inspect::<Wrapper<Id>>();
+    assert_inspect::<Fields<Id, u8>>();
+
+    let wrapper = Wrapper::<Id> {
+        field: Values { values: vec![1, 2] },
+    };
+    let mut names = Names::default();
+    wrapper.visit(&mut names);
+    assert_eq!(names.0, ["field", "values", "values"]);
+    assert_eq!(format!("{:?}", wrapper), "Wrapper { field: Values { values: [1, 2] } }");
+}
@@ -0,0 +1,22 @@
+// A skipped or redacted field can't be printed by another field through
+// {self.other}, that would show exactly what the attribute hides.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Login {
+    #[debug = "{} (pw {self.password})"]
+    user: String,
+    #[debug(redact)]
+    password: String,
+}
+
+#[derive(CustomDebug)]
+pub struct Session {
+    #[debug = "{} of {self.token}"]
+    id: u64,
+    #[debug(skip)]
+    token: String,
+}
+
+fn main() {}
@@ -9,4 +9,23 @@ fn tests() {
     t.pass("tests/06-bound-trouble.rs");
     t.pass("tests/07-associated-type.rs");
     t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-skip-redact.rs");
+    t.pass("tests/10-custom-formatter.rs");
+    t.pass("tests/11-multiple-attributes.rs");
+    t.compile_fail("tests/12-duplicate-attribute.rs");
+    t.compile_fail("tests/13-non-string-format.rs");
+    t.pass("tests/14-field-bounds.rs");
+    t.pass("tests/15-nested-generics.rs");
+    t.pass("tests/16-custom-display.rs");
+    t.compile_fail("tests/17-display-unknown-field.rs");
+    t.pass("tests/18-format-reference.rs");
+    t.compile_fail("tests/19-invalid-format.rs");
+    t.pass("tests/20-styles.rs");
+    t.pass("tests/21-no-std.rs");
+    t.pass("tests/22-skip-if-truncate.rs");
+    t.pass("tests/23-union.rs");
+    t.compile_fail("tests/24-union-without-formatter.rs");
+    t.pass("tests/25-inspect.rs");
+    t.pass("tests/26-inspect-bounds.rs");
+    t.compile_fail("tests/27-hidden-reference.rs");
 }
@@ -0,0 +1,72 @@
+// Times the expansion of bodies repeated 256 and 65536 times, outside of the
+// compiler since a proc-macro crate can't be linked to. Run with:
+//
+//     cargo bench -p seq
+
+#![allow(dead_code)]
+
+#[path = "../src/binding.rs"]
+mod binding;
+#[path = "../src/expr.rs"]
+mod expr;
+#[path = "../src/item.rs"]
+mod item;
+#[path = "../src/list.rs"]
+mod list;
+#[path = "../src/range.rs"]
+mod range;
+#[path = "../src/sequence.rs"]
+mod sequence;
+#[path = "../src/template.rs"]
+mod template;
+
+use sequence::Sequence;
+use std::time::{Duration, Instant};
+
+/// Repeated as a whole.
+const WHOLE_BODY: &str = "
+    #[inline]
+    pub fn f~N(x: u64) -> u64 {
+        let table = [1u64, 2, 3, 4, 5, 6, 7, 8];
+        x * N + table[#{N % 8}] + REG_~N:04
+    }
+";
+
+/// Repeated in a section, most of the body being kept once.
+const SECTION: &str = "
+    pub enum Interrupt {
+        #(Irq~N = N,)*
+    }
+";
+
+fn main() {
+    for (name, body) in [("whole body", WHOLE_BODY), ("section", SECTION)] {
+        for iterations in [256, 65536] {
+            let time = expand(body, iterations);
+            println!(
+                "{:<10} {:>6} iterations: {:>10.3?} total, {:>8.3?} per iteration",
+                name,
+                iterations,
+                time,
+                time / iterations,
+            );
+        }
+    }
+}
+
+/// The best of a few expansions.
+fn expand(body: &str, iterations: u32) -> Duration {
+    let runs = if iterations > 1024 { 3 } else { 50 };
+    (0..runs)
+        .map(|_| {
+            let input = format!("N in 0..{} {{ {} }}", iterations, body);
+            let mut seq = syn::parse_str::<Sequence>(&input).unwrap();
+            let start = Instant::now();
+            let tokens = seq.expand().unwrap();
+            let time = start.elapsed();
+            assert!(!tokens.is_empty());
+            time
+        })
+        .min()
+        .unwrap()
+}
@@ -0,0 +1,253 @@
+use crate::list::List;
+use crate::range::{LitKind, Range};
+use proc_macro2::{Delimiter, Ident, TokenStream, TokenTree};
+use syn::{parenthesized, parse::Parse, punctuated::Punctuated, Token};
+
+/// A loop variable.
+#[derive(Clone)]
+pub(crate) struct Var {
+    pub(crate) name: syn::Ident,
+    /// `N as hex`, pastes the values as uppercase hexadecimal digits.
+    pub(crate) paste_hex: bool,
+}
+
+// ```rust, ignore
+// N
+// N as hex
+// ```
+impl Parse for Var {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let name: syn::Ident = input.parse()?;
+        let paste_hex = if input.parse::<Option<Token![as]>>()?.is_some() {
+            let radix: syn::Ident = input.parse()?;
+            if radix != "hex" {
+                return Err(syn::Error::new(radix.span(), "expected `hex`"));
+            }
+            true
+        } else {
+            false
+        };
+        Ok(Self { name, paste_hex })
+    }
+}
+
+/// Where the values of a loop variable come from.
+pub(crate) enum Source {
+    Range(Range),
+    List(List),
+}
+
+impl Source {
+    fn len(&self) -> u128 {
+        match self {
+            Source::Range(range) => range.len(),
+            Source::List(list) => list.len() as u128,
+        }
+    }
+
+    fn items(&self) -> Box<dyn Iterator<Item = Item<'_>> + '_> {
+        match self {
+            Source::Range(range) => Box::new(
+                range
+                    .values()
+                    .map(move |value| Item::Int(value, &range.kind)),
+            ),
+            Source::List(list) => Box::new(list.items()),
+        }
+    }
+}
+
+// ```rust, ignore
+// 0..4
+// [u8, u16, u32]
+// {1, 2, 4, 8}
+// ```
+impl Parse for Source {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::token::Bracket) || input.peek(syn::token::Brace) {
+            Ok(Source::List(input.parse()?))
+        } else {
+            Ok(Source::Range(input.parse()?))
+        }
+    }
+}
+
+/// One `... in ...` clause of the header, binding each of `vars` to the values
+/// of the matching source. Zipped sources stop with the shortest one.
+pub(crate) struct Binding {
+    vars: Vec<Var>,
+    sources: Vec<Source>,
+}
+
+impl Binding {
+    pub(crate) fn new(var: Var, source: Source) -> Self {
+        Self {
+            vars: vec![var],
+            sources: vec![source],
+        }
+    }
+
+    pub(crate) fn vars(&self) -> &[Var] {
+        &self.vars
+    }
+}
+
+// ```rust, ignore
+// N in 0..4
+// T in [u8, u16, u32]
+// i, T in [u8, u16, u32]
+// (A, B) in zip(0..4, 10..14)
+// ```
+//
+// `i, T in ...` also binds `i` to the index of each value, starting at 0.
+impl Parse for Binding {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if !input.peek(syn::token::Paren) {
+            let var: Var = input.parse()?;
+            if input.parse::<Option<Token![,]>>()?.is_none() {
+                input.parse::<Token![in]>()?;
+                return Ok(Self::new(var, input.parse()?));
+            }
+            let index = var;
+            let var: Var = input.parse()?;
+            input.parse::<Token![in]>()?;
+            let source: Source = input.parse()?;
+            return Ok(Self {
+                vars: vec![index, var],
+                sources: vec![Source::Range(Range::indices(source.len())), source],
+            });
+        }
+
+        let content;
+        let paren = parenthesized!(content in input);
+        let vars = Punctuated::<Var, Token![,]>::parse_terminated(&content)?;
+        input.parse::<Token![in]>()?;
+        let zip: syn::Ident = input.parse()?;
+        if zip != "zip" {
+            return Err(syn::Error::new(zip.span(), "expected `zip(..)`"));
+        }
+        let content;
+        parenthesized!(content in input);
+        let sources = Punctuated::<Source, Token![,]>::parse_terminated(&content)?;
+
+        if vars.len() != sources.len() {
+            return Err(syn::Error::new(
+                paren.span,
+                format!(
+                    "{} variables are bound to {} zipped ranges",
+                    vars.len(),
+                    sources.len()
+                ),
+            ));
+        }
+        Ok(Self {
+            vars: vars.into_iter().collect(),
+            sources: sources.into_iter().collect(),
+        })
+    }
+}
+
+/// What a loop variable is replaced with during one iteration.
+#[derive(Clone, Copy)]
+pub(crate) enum Item<'a> {
+    /// A number, written like the bounds of its range.
+    Int(i128, &'a LitKind),
+    /// An element of a list that isn't an integer literal.
+    Tokens(&'a TokenStream),
+}
+
+/// The value of a loop variable during one iteration.
+#[derive(Clone, Copy)]
+pub(crate) struct Value<'a> {
+    pub(crate) var: &'a Var,
+    pub(crate) item: Item<'a>,
+}
+
+/// The variables bound by the header of a nested `seq!`, found without
+/// parsing it since its bounds may still refer to the outer variables.
+///
+/// ```text
+/// I in 0..N, (A, B) in zip(..) { ... }   -> I, A, B
+/// i, T in [u8, u16] { ... }              -> i, T
+/// ```
+pub(crate) fn bound_names(invocation: TokenStream) -> Vec<Ident> {
+    let mut names = Vec::new();
+    let mut candidates = Vec::new();
+    let mut in_source = false;
+    for token in invocation {
+        match token {
+            // The `,` between two bindings.
+            TokenTree::Punct(punct) if in_source && punct.as_char() == ',' => {
+                in_source = false;
+            }
+            _ if in_source => {}
+            TokenTree::Ident(ident) if ident == "in" => {
+                names.append(&mut candidates);
+                in_source = true;
+            }
+            TokenTree::Ident(ident) if ident != "as" && ident != "hex" => candidates.push(ident),
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => candidates
+                .extend(group.stream().into_iter().filter_map(|token| match token {
+                    TokenTree::Ident(ident) if ident != "as" && ident != "hex" => Some(ident),
+                    _ => None,
+                })),
+            _ => {}
+        }
+    }
+    names
+}
+
+/// The number of iterations of `bindings`, without going through them.
+pub(crate) fn count(bindings: &[Binding]) -> u128 {
+    bindings
+        .iter()
+        .map(|binding| binding.sources.iter().map(Source::len).min().unwrap_or(0))
+        .fold(1, u128::saturating_mul)
+}
+
+/// Every combination of values taken by the variables of `bindings`, the
+/// first binding being the outermost loop.
+///
+/// ```text
+/// I in 0..2, J in 0..2      -> [I=0, J=0], [I=0, J=1], [I=1, J=0], [I=1, J=1]
+/// (A, B) in zip(0..2, 5..9) -> [A=0, B=5], [A=1, B=6]
+/// i, T in [u8, u16]         -> [i=0, T=u8], [i=1, T=u16]
+/// ```
+pub(crate) fn iterations(bindings: &[Binding]) -> Vec<Vec<Value<'_>>> {
+    let mut iterations = vec![Vec::new()];
+
+    for binding in bindings {
+        let mut items = binding
+            .sources
+            .iter()
+            .map(Source::items)
+            .collect::<Vec<_>>();
+        let mut rows = Vec::new();
+        while let Some(row) = items
+            .iter_mut()
+            .map(Iterator::next)
+            .collect::<Option<Vec<_>>>()
+        {
+            rows.push(row);
+        }
+
+        iterations = iterations
+            .into_iter()
+            .flat_map(|outer: Vec<Value>| {
+                rows.iter().map(move |row| {
+                    let mut env = outer.clone();
+                    env.extend(
+                        binding
+                            .vars
+                            .iter()
+                            .zip(row)
+                            .map(|(var, item)| Value { var, item: *item }),
+                    );
+                    env
+                })
+            })
+            .collect();
+    }
+
+    iterations
+}
@@ -0,0 +1,434 @@
+use crate::binding::{Item, Value};
+use crate::range::LitKind;
+use proc_macro2::{Delimiter, Group, Ident, Spacing, Span, TokenStream, TokenTree};
+use syn::LitInt;
+
+/// The result of a constant expression.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Const {
+    Int(i128),
+    Bool(bool),
+}
+
+/// An evaluated `#{...}` or `~{...}`.
+pub(crate) struct Evaluated {
+    pub(crate) value: Const,
+    /// Radix and suffix of the first suffixed operand, or else of the first
+    /// loop variable.
+    pub(crate) kind: LitKind,
+    /// Whether a loop variable bound with `as hex` appears in the expression.
+    pub(crate) paste_hex: bool,
+}
+
+impl Evaluated {
+    /// `true`, `false` or an integer literal.
+    pub(crate) fn to_token(&self, span: Span) -> syn::Result<TokenTree> {
+        match self.value {
+            Const::Int(value) => {
+                self.kind.check(value, span)?;
+                Ok(self.kind.token(value, span))
+            }
+            Const::Bool(value) => Ok(TokenTree::from(Ident::new(&value.to_string(), span))),
+        }
+    }
+}
+
+// ```rust, ignore
+// #{N * 2 + 1}
+// #{1 << N}
+// #{N % 2 == 0}
+// #{u8::MAX - N}
+// f~{N + 1}
+// ```
+//
+// Integers are evaluated as `i128` with every operation checked, and the
+// result must fit in the type of the operands if they have a suffix. The
+// precedence and associativity of the operators are the ones of Rust.
+pub(crate) fn evaluate(group: &Group, env: &[Value]) -> syn::Result<Evaluated> {
+    Expr::parse(group)?.evaluate(env)
+}
+
+/// A `#{...}` or `~{...}` parsed once, so that it can be evaluated with the
+/// values of every iteration without reading its tokens again.
+///
+/// ```text
+/// #{N * 2 + 1}   operands: [N, 2, 1]   root: Binary(+, Binary(*, 0, 1), 2)
+/// ```
+pub(crate) struct Expr {
+    /// The literals, loop variables and parenthesized expressions, in the
+    /// order they are written.
+    operands: Vec<(Operand, Span)>,
+    root: Node,
+}
+
+enum Operand {
+    /// A literal, `true`, `false`, `u8::MAX`...
+    Const(Const, LitKind),
+    Var(Ident),
+    Group(Expr),
+}
+
+enum Node {
+    /// Index in `Expr::operands`.
+    Operand(usize),
+    Unary(&'static str, Box<Node>, Span),
+    Binary(&'static str, Box<Node>, Box<Node>, Span),
+}
+
+impl Expr {
+    pub(crate) fn parse(group: &Group) -> syn::Result<Self> {
+        let mut operands = Vec::new();
+        let tokens = tokenize(group.stream(), &mut operands)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: group.span_close(),
+        };
+        let root = parser.expr(0)?;
+        if let Some(tok) = parser.tokens.get(parser.pos) {
+            return Err(syn::Error::new(tok.span(), "expected an operator"));
+        }
+        Ok(Self { operands, root })
+    }
+
+    /// The operands are all looked up before any operation is computed, so an
+    /// unknown loop variable is reported before an overflow.
+    pub(crate) fn evaluate(&self, env: &[Value]) -> syn::Result<Evaluated> {
+        let mut values = Vec::with_capacity(self.operands.len());
+        let mut kind: Option<LitKind> = None;
+        let mut paste_hex = false;
+        for (operand, span) in &self.operands {
+            let group;
+            let (value, operand_kind, operand_hex) = match operand {
+                Operand::Const(value, kind) => (*value, kind, false),
+                Operand::Var(ident) => {
+                    let value = env
+                        .iter()
+                        .rev()
+                        .find(|value| value.var.name == *ident)
+                        .ok_or_else(|| {
+                            syn::Error::new(*span, format!("unknown loop variable `{}`", ident))
+                        })?;
+                    match value.item {
+                        Item::Int(number, kind) => (Const::Int(number), kind, value.var.paste_hex),
+                        Item::Tokens(tokens) => {
+                            return Err(syn::Error::new(
+                                *span,
+                                format!("`{}` is `{}`, not a number", ident, tokens),
+                            ))
+                        }
+                    }
+                }
+                Operand::Group(expr) => {
+                    group = expr.evaluate(env)?;
+                    (group.value, &group.kind, group.paste_hex)
+                }
+            };
+            // The first suffixed operand gives the kind, or else the first one.
+            let replace = match &kind {
+                Some(current) => !current.has_suffix() && operand_kind.has_suffix(),
+                None => true,
+            };
+            if replace {
+                kind = Some(operand_kind.clone());
+            }
+            paste_hex |= operand_hex;
+            values.push(value);
+        }
+        Ok(Evaluated {
+            value: self.root.compute(&values)?,
+            kind: kind.unwrap_or_default(),
+            paste_hex,
+        })
+    }
+}
+
+impl Node {
+    fn compute(&self, values: &[Const]) -> syn::Result<Const> {
+        match self {
+            Node::Operand(index) => Ok(values[*index]),
+            Node::Unary(op, operand, span) => unary(op, operand.compute(values)?, *span),
+            Node::Binary(op, lhs, rhs, span) => {
+                binary(op, lhs.compute(values)?, rhs.compute(values)?, *span)
+            }
+        }
+    }
+}
+
+enum Tok {
+    /// Index of a literal, a loop variable or a parenthesized expression in
+    /// the operands.
+    Operand(usize, Span),
+    Op(&'static str, Span),
+}
+
+impl Tok {
+    fn span(&self) -> Span {
+        match self {
+            Tok::Operand(_, span) | Tok::Op(_, span) => *span,
+        }
+    }
+}
+
+const OPS: [&str; 19] = [
+    "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "&", "|", "^", "<",
+    ">", "!",
+];
+
+/// Moves the operands to `operands`, the tokens only keep their index.
+fn tokenize(stream: TokenStream, operands: &mut Vec<(Operand, Span)>) -> syn::Result<Vec<Tok>> {
+    let mut tokens = Vec::new();
+    let mut token_iter = stream.into_iter().peekable();
+
+    while let Some(token) = token_iter.next() {
+        let span = token.span();
+        let operand = match token {
+            TokenTree::Literal(lit) => {
+                let lit: LitInt = syn::parse2(TokenTree::from(lit).into())?;
+                Operand::Const(Const::Int(lit.base10_parse()?), LitKind::of(&lit))
+            }
+            TokenTree::Ident(ident) if ident == "true" || ident == "false" => {
+                Operand::Const(Const::Bool(ident == "true"), LitKind::default())
+            }
+            // u64::MAX
+            TokenTree::Ident(ident) if int_bounds(&ident.to_string()).is_some() => {
+                let (Some(TokenTree::Punct(first)), Some(TokenTree::Punct(second))) =
+                    (token_iter.next(), token_iter.next())
+                else {
+                    return Err(syn::Error::new(
+                        span,
+                        "expected `MAX` or `MIN` after the type",
+                    ));
+                };
+                let limit = match token_iter.next() {
+                    Some(TokenTree::Ident(limit))
+                        if first.as_char() == ':'
+                            && first.spacing() == Spacing::Joint
+                            && second.as_char() == ':' =>
+                    {
+                        limit
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            span,
+                            "expected `MAX` or `MIN` after the type",
+                        ))
+                    }
+                };
+                let (min, max) = int_bounds(&ident.to_string()).unwrap();
+                let value = if limit == "MIN" {
+                    min
+                } else if limit == "MAX" {
+                    max.ok_or_else(|| {
+                        syn::Error::new(
+                            limit.span(),
+                            format!("`{}::MAX` does not fit in `i128`", ident),
+                        )
+                    })?
+                } else {
+                    return Err(syn::Error::new(limit.span(), "expected `MAX` or `MIN`"));
+                };
+                Operand::Const(Const::Int(value), LitKind::suffixed(&ident.to_string()))
+            }
+            TokenTree::Ident(ident) => Operand::Var(ident),
+            TokenTree::Group(group)
+                if matches!(group.delimiter(), Delimiter::Parenthesis | Delimiter::None) =>
+            {
+                Operand::Group(Expr::parse(&group)?)
+            }
+            TokenTree::Punct(punct) => {
+                let mut op = punct.as_char().to_string();
+                if punct.spacing() == Spacing::Joint {
+                    if let Some(TokenTree::Punct(next)) = token_iter.peek() {
+                        let joined = format!("{}{}", op, next.as_char());
+                        if OPS.contains(&joined.as_str()) {
+                            op = joined;
+                            token_iter.next();
+                        }
+                    }
+                }
+                match OPS.iter().find(|known| **known == op) {
+                    Some(op) => tokens.push(Tok::Op(op, span)),
+                    None => {
+                        return Err(syn::Error::new(
+                            span,
+                            format!("unsupported operator `{}`", op),
+                        ))
+                    }
+                }
+                continue;
+            }
+            other => {
+                return Err(syn::Error::new(
+                    span,
+                    format!("unexpected `{}` in expression", other),
+                ))
+            }
+        };
+        tokens.push(Tok::Operand(operands.len(), span));
+        operands.push((operand, span));
+    }
+
+    Ok(tokens)
+}
+
+/// `MIN` and `MAX` of the integer types, `u128::MAX` not being representable.
+pub(crate) fn int_bounds(ty: &str) -> Option<(i128, Option<i128>)> {
+    let (min, max) = match ty {
+        "i8" => (i8::MIN as i128, i8::MAX as i128),
+        "i16" => (i16::MIN as i128, i16::MAX as i128),
+        "i32" => (i32::MIN as i128, i32::MAX as i128),
+        "i64" | "isize" => (i64::MIN as i128, i64::MAX as i128),
+        "i128" => (i128::MIN, i128::MAX),
+        "u8" => (0, u8::MAX as i128),
+        "u16" => (0, u16::MAX as i128),
+        "u32" => (0, u32::MAX as i128),
+        "u64" | "usize" => (0, u64::MAX as i128),
+        "u128" => return Some((0, None)),
+        _ => return None,
+    };
+    Some((min, Some(max)))
+}
+
+struct Parser {
+    tokens: Vec<Tok>,
+    pos: usize,
+    /// Where a missing operand is reported.
+    end: Span,
+}
+
+const UNARY_BINDING_POWER: u8 = 20;
+
+/// Left binding power of the binary operators, from the Rust precedence table.
+fn binding_power(op: &str) -> Option<u8> {
+    let power = match op {
+        "||" => 1,
+        "&&" => 2,
+        "==" | "!=" | "<" | ">" | "<=" | ">=" => 3,
+        "|" => 4,
+        "^" => 5,
+        "&" => 6,
+        "<<" | ">>" => 7,
+        "+" | "-" => 8,
+        "*" | "/" | "%" => 9,
+        _ => return None,
+    };
+    Some(power * 2)
+}
+
+impl Parser {
+    fn expr(&mut self, min_power: u8) -> syn::Result<Node> {
+        let mut lhs = match self.tokens.get(self.pos) {
+            Some(Tok::Operand(index, _)) => {
+                let index = *index;
+                self.pos += 1;
+                Node::Operand(index)
+            }
+            Some(Tok::Op(op @ ("-" | "!"), span)) => {
+                let (op, span) = (*op, *span);
+                self.pos += 1;
+                let operand = self.expr(UNARY_BINDING_POWER)?;
+                Node::Unary(op, Box::new(operand), span)
+            }
+            Some(Tok::Op(op, span)) => {
+                return Err(syn::Error::new(
+                    *span,
+                    format!("expected an operand, found `{}`", op),
+                ))
+            }
+            None => return Err(syn::Error::new(self.end, "expected an operand")),
+        };
+
+        loop {
+            let (op, span) = match self.tokens.get(self.pos) {
+                Some(Tok::Op(op, span)) => (*op, *span),
+                Some(Tok::Operand(_, span)) => {
+                    return Err(syn::Error::new(*span, "expected an operator"))
+                }
+                None => break,
+            };
+            let power = binding_power(op).ok_or_else(|| {
+                syn::Error::new(span, format!("`{}` is not a binary operator", op))
+            })?;
+            if power < min_power {
+                break;
+            }
+            self.pos += 1;
+            // Left associative: the right operand only takes tighter operators.
+            let rhs = self.expr(power + 1)?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs), span);
+        }
+
+        Ok(lhs)
+    }
+}
+
+fn unary(op: &str, operand: Const, span: Span) -> syn::Result<Const> {
+    match (op, operand) {
+        ("-", Const::Int(value)) => value
+            .checked_neg()
+            .map(Const::Int)
+            .ok_or_else(|| overflow(span, format!("-{}", value))),
+        ("!", Const::Int(value)) => Ok(Const::Int(!value)),
+        ("!", Const::Bool(value)) => Ok(Const::Bool(!value)),
+        _ => Err(syn::Error::new(
+            span,
+            format!("cannot apply `{}` to a boolean", op),
+        )),
+    }
+}
+
+fn binary(op: &str, lhs: Const, rhs: Const, span: Span) -> syn::Result<Const> {
+    let (lhs, rhs) = match (op, lhs, rhs) {
+        ("==", lhs, rhs) => return Ok(Const::Bool(lhs == rhs)),
+        ("!=", lhs, rhs) => return Ok(Const::Bool(lhs != rhs)),
+        ("&&", Const::Bool(lhs), Const::Bool(rhs)) => return Ok(Const::Bool(lhs && rhs)),
+        ("||", Const::Bool(lhs), Const::Bool(rhs)) => return Ok(Const::Bool(lhs || rhs)),
+        ("&&" | "||", _, _) => {
+            return Err(syn::Error::new(span, format!("`{}` expects booleans", op)))
+        }
+        (_, Const::Int(lhs), Const::Int(rhs)) => (lhs, rhs),
+        _ => return Err(syn::Error::new(span, format!("`{}` expects integers", op))),
+    };
+
+    let value = match op {
+        "<" => return Ok(Const::Bool(lhs < rhs)),
+        ">" => return Ok(Const::Bool(lhs > rhs)),
+        "<=" => return Ok(Const::Bool(lhs <= rhs)),
+        ">=" => return Ok(Const::Bool(lhs >= rhs)),
+        "+" => lhs.checked_add(rhs),
+        "-" => lhs.checked_sub(rhs),
+        "*" => lhs.checked_mul(rhs),
+        "/" | "%" if rhs == 0 => {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "attempt to compute `{} {} 0`, which divides by zero",
+                    lhs, op
+                ),
+            ))
+        }
+        "/" => lhs.checked_div(rhs),
+        "%" => lhs.checked_rem(rhs),
+        "&" => Some(lhs & rhs),
+        "|" => Some(lhs | rhs),
+        "^" => Some(lhs ^ rhs),
+        // Unlike `checked_shl`, also rejects the bits shifted out.
+        "<<" => u32::try_from(rhs)
+            .ok()
+            .and_then(|rhs| lhs.checked_shl(rhs))
+            .filter(|value| value >> rhs == lhs),
+        ">>" => u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shr(rhs)),
+        _ => unreachable!("not a binary operator: {}", op),
+    };
+    value
+        .map(Const::Int)
+        .ok_or_else(|| overflow(span, format!("{} {} {}", lhs, op, rhs)))
+}
+
+fn overflow(span: Span, expr: String) -> syn::Error {
+    syn::Error::new(
+        span,
+        format!("attempt to compute `{}`, which would overflow", expr),
+    )
+}
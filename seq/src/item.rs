@@ -0,0 +1,263 @@
+use crate::binding::{count, iterations};
+use crate::sequence::{in_iteration, Sequence};
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{spanned::Spanned, Attribute, Field, Fields, ImplItem, Item, Variant};
+
+/// A variant, a field or an associated item, which `#[each]` may repeat.
+trait Element: ToTokens + Clone + Sized {
+    fn attrs(&mut self) -> Option<&mut Vec<Attribute>>;
+
+    /// Replaces the name the element is written with.
+    fn rename(&mut self, name: Ident) -> syn::Result<()>;
+
+    /// Parses a copy back after its variables are replaced.
+    fn reparse(tokens: TokenStream) -> syn::Result<Self>;
+}
+
+impl Element for Variant {
+    fn attrs(&mut self) -> Option<&mut Vec<Attribute>> {
+        Some(&mut self.attrs)
+    }
+
+    fn rename(&mut self, name: Ident) -> syn::Result<()> {
+        self.ident = name;
+        Ok(())
+    }
+
+    fn reparse(tokens: TokenStream) -> syn::Result<Self> {
+        syn::parse2(tokens)
+    }
+}
+
+impl Element for Field {
+    fn attrs(&mut self) -> Option<&mut Vec<Attribute>> {
+        Some(&mut self.attrs)
+    }
+
+    fn rename(&mut self, name: Ident) -> syn::Result<()> {
+        match &mut self.ident {
+            Some(ident) => {
+                *ident = name;
+                Ok(())
+            }
+            None => Err(syn::Error::new(
+                name.span(),
+                "a tuple struct field has no name to replace, use `#[each]`",
+            )),
+        }
+    }
+
+    fn reparse(tokens: TokenStream) -> syn::Result<Self> {
+        syn::parse::Parser::parse2(Field::parse_named, tokens.clone())
+            .or_else(|_| syn::parse::Parser::parse2(Field::parse_unnamed, tokens))
+    }
+}
+
+impl Element for ImplItem {
+    fn attrs(&mut self) -> Option<&mut Vec<Attribute>> {
+        match self {
+            ImplItem::Const(item) => Some(&mut item.attrs),
+            ImplItem::Method(item) => Some(&mut item.attrs),
+            ImplItem::Type(item) => Some(&mut item.attrs),
+            ImplItem::Macro(item) => Some(&mut item.attrs),
+            _ => None,
+        }
+    }
+
+    fn rename(&mut self, name: Ident) -> syn::Result<()> {
+        match self {
+            ImplItem::Const(item) => item.ident = name,
+            ImplItem::Method(item) => item.sig.ident = name,
+            ImplItem::Type(item) => item.ident = name,
+            _ => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "only constants, functions and types can be renamed",
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn reparse(tokens: TokenStream) -> syn::Result<Self> {
+        syn::parse2(tokens)
+    }
+}
+
+// ```rust, ignore
+// #[repeat(N in 0..4)]
+// struct Registers {
+//     #[each(reg~N)]
+//     reg: u32,
+//     status: u32,
+// }
+//
+// struct Registers {
+//     reg0: u32,
+//     reg1: u32,
+//     reg2: u32,
+//     reg3: u32,
+//     status: u32,
+// }
+// ```
+//
+// Every copy of a marked element has its loop variables replaced like the body
+// of a `seq!`, and the loop variables can't be used outside of the marked
+// elements.
+pub(crate) fn expand(seq: &Sequence, item: Item) -> syn::Result<TokenStream> {
+    let mut found = false;
+    let item = match item {
+        Item::Enum(mut item) => {
+            item.variants = repeat_all(seq, item.variants, &mut found)?
+                .into_iter()
+                .collect();
+            Item::Enum(item)
+        }
+        Item::Struct(mut item) => {
+            match &mut item.fields {
+                Fields::Named(fields) => {
+                    let named = std::mem::take(&mut fields.named);
+                    fields.named = repeat_all(seq, named, &mut found)?.into_iter().collect();
+                }
+                Fields::Unnamed(fields) => {
+                    let unnamed = std::mem::take(&mut fields.unnamed);
+                    fields.unnamed = repeat_all(seq, unnamed, &mut found)?.into_iter().collect();
+                }
+                Fields::Unit => {}
+            }
+            Item::Struct(item)
+        }
+        Item::Impl(mut item) => {
+            let items = std::mem::take(&mut item.items);
+            item.items = repeat_all(seq, items, &mut found)?;
+            Item::Impl(item)
+        }
+        other => {
+            return Err(syn::Error::new(
+                other.span(),
+                "`#[repeat]` can only be placed on an enum, a struct or an impl block",
+            ))
+        }
+    };
+    if !found {
+        return Err(syn::Error::new(
+            seq.span,
+            "no variant, field or associated item is marked with `#[each]`",
+        ));
+    }
+
+    // The copies don't contain any loop variable anymore.
+    let tokens = item.into_token_stream();
+    if let Some(ident) = find_stray(seq, tokens.clone()) {
+        return Err(syn::Error::new(
+            ident.span(),
+            format!(
+                "`{}` is used outside of the elements marked with `#[each]`",
+                ident
+            ),
+        ));
+    }
+    Ok(tokens)
+}
+
+/// The first loop variable in `tokens`, which are only scanned: a `#(...)*` in
+/// an unmarked element belongs to some other macro. The variables of a nested
+/// `seq!` and `$N` metavariables are not loop variables.
+fn find_stray(seq: &Sequence, tokens: TokenStream) -> Option<Ident> {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) if ident == "seq" => {
+                if matches!(tokens.peek(), Some(TokenTree::Punct(bang)) if bang.as_char() == '!') {
+                    tokens.next(); // Skip '!'
+                    tokens.next(); // Skip (...)
+                }
+            }
+            TokenTree::Ident(ident) if seq.is_header_var(&ident) => return Some(ident),
+            TokenTree::Punct(punct) if punct.as_char() == '$' => {
+                if matches!(tokens.peek(), Some(TokenTree::Ident(_))) {
+                    tokens.next(); // Skip the metavariable
+                }
+            }
+            TokenTree::Group(group) => {
+                if let Some(ident) = find_stray(seq, group.stream()) {
+                    return Some(ident);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Replaces each marked element with its copies.
+fn repeat_all<T: Element>(
+    seq: &Sequence,
+    elements: impl IntoIterator<Item = T>,
+    found: &mut bool,
+) -> syn::Result<Vec<T>> {
+    let mut output = Vec::new();
+    for mut element in elements {
+        let marker = match element.attrs() {
+            Some(attrs) => take_marker(attrs)?,
+            None => None,
+        };
+        let (span, name) = match marker {
+            Some(marker) => marker,
+            None => {
+                output.push(element);
+                continue;
+            }
+        };
+        *found = true;
+
+        seq.reserve(count(&seq.bindings), span)?;
+        for env in iterations(&seq.bindings) {
+            let expand = |mut copy: T| {
+                if let Some(name) = &name {
+                    let pasted = seq.replace_number(name.clone(), &env, true)?;
+                    copy.rename(single_ident(pasted, span)?)?;
+                }
+                let tokens = seq.replace_number(copy.into_token_stream(), &env, true)?;
+                T::reparse(tokens)
+            };
+            output.push(expand(element.clone()).map_err(|err| in_iteration(err, &env))?);
+        }
+    }
+    Ok(output)
+}
+
+/// Takes the `#[each]` or `#[each(name)]` out of `attrs`, giving where it is
+/// and the name template.
+fn take_marker(attrs: &mut Vec<Attribute>) -> syn::Result<Option<(Span, Option<TokenStream>)>> {
+    let index = match attrs.iter().position(|attr| attr.path.is_ident("each")) {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+    let attr = attrs.remove(index);
+    let span = attr.span();
+
+    let mut tokens = attr.tokens.into_iter();
+    match (tokens.next(), tokens.next()) {
+        (None, _) => Ok(Some((span, None))),
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::Parenthesis => {
+            Ok(Some((span, Some(group.stream()))))
+        }
+        _ => Err(syn::Error::new(
+            span,
+            "expected `#[each]` or `#[each(name)]`, such as `#[each(Irq~N)]`",
+        )),
+    }
+}
+
+fn single_ident(tokens: TokenStream, span: Span) -> syn::Result<Ident> {
+    let mut tokens = tokens.into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(ident)), None) => Ok(ident),
+        _ => Err(syn::Error::new(
+            span,
+            "expected a single identifier, such as `Irq~N`",
+        )),
+    }
+}
@@ -0,0 +1,92 @@
+use crate::binding::Item;
+use crate::range::LitKind;
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use syn::{braced, bracketed, parse::Parse, LitInt, Token};
+
+/// The elements of `[...]` or `{...}`, in order.
+pub(crate) struct List {
+    elements: Vec<Element>,
+}
+
+enum Element {
+    /// `4`, `-4` or `0x04u8`, usable in `#{...}` and pasted as a number.
+    Int(i128, LitKind),
+    /// Anything else, such as a type or an identifier.
+    Tokens(TokenStream),
+}
+
+impl List {
+    pub(crate) fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub(crate) fn items(&self) -> impl Iterator<Item = Item<'_>> {
+        self.elements.iter().map(|element| match element {
+            Element::Int(value, kind) => Item::Int(*value, kind),
+            Element::Tokens(tokens) => Item::Tokens(tokens),
+        })
+    }
+}
+
+// ```rust, ignore
+// seq!(T in [u8, u16, u32, u64] { ... });
+// seq!(N in {1, 2, 4, 8} { ... });
+// seq!(T in [Vec<u8>, HashMap<u8, u8>] { ... });
+// ```
+//
+// Both delimiters mean the same. The elements are separated by the commas
+// that aren't inside a generic argument list.
+impl Parse for List {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let content;
+        if input.peek(syn::token::Bracket) {
+            bracketed!(content in input);
+        } else {
+            braced!(content in input);
+        }
+
+        let mut elements = Vec::new();
+        while !content.is_empty() {
+            let span = content.span();
+            let mut tokens = TokenStream::new();
+            let mut depth = 0usize;
+            let mut arrow = false;
+            while !content.is_empty() && (depth > 0 || !content.peek(Token![,])) {
+                let token = content.parse::<TokenTree>()?;
+                if let TokenTree::Punct(punct) = &token {
+                    match punct.as_char() {
+                        '<' => depth += 1,
+                        // Not the end of `Fn() -> T`.
+                        '>' if !arrow => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                    arrow = punct.as_char() == '-' && punct.spacing() == Spacing::Joint;
+                } else {
+                    arrow = false;
+                }
+                tokens.extend([token]);
+            }
+            if tokens.is_empty() {
+                return Err(syn::Error::new(span, "expected an element"));
+            }
+            elements.push(element(tokens));
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+        Ok(Self { elements })
+    }
+}
+
+fn element(tokens: TokenStream) -> Element {
+    let int = |input: syn::parse::ParseStream| {
+        let negative = input.parse::<Option<Token![-]>>()?.is_some();
+        let lit = input.parse::<LitInt>()?;
+        let value: i128 = lit.base10_parse()?;
+        Ok((if negative { -value } else { value }, LitKind::of(&lit)))
+    };
+    match syn::parse::Parser::parse2(int, tokens.clone()) {
+        Ok((value, kind)) => Element::Int(value, kind),
+        Err(_) => Element::Tokens(tokens),
+    }
+}
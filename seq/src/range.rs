@@ -0,0 +1,335 @@
+use crate::expr::{evaluate, int_bounds, Const};
+use proc_macro2::{Delimiter, Group, Literal, Span, TokenStream, TokenTree};
+use syn::{parenthesized, parse::Parse, LitInt, Token};
+
+/// The values taken by the loop variable, an arithmetic progression of `len`
+/// values starting at `first`.
+///
+/// ```text
+/// 0..4                   -> first: 0, step: 1, len: 4    0, 1, 2, 3
+/// -2..=2                 -> first: -2, step: 1, len: 5   -2, -1, 0, 1, 2
+/// (0..64).step_by(16)    -> first: 0, step: 16, len: 4   0, 16, 32, 48
+/// 0..64 ; 16             -> same as above
+/// (0..4).rev()           -> first: 3, step: -1, len: 4   3, 2, 1, 0
+/// ```
+#[derive(Clone)]
+pub(crate) struct Range {
+    first: i128,
+    step: i128,
+    len: u128,
+    /// How the values are written, taken from the bounds.
+    pub(crate) kind: LitKind,
+}
+
+// The distances between the values are computed with wrapping arithmetic since
+// they may not fit in `i128`, while the values they lead to always do.
+impl Range {
+    /// `0..len`, the indices of a list.
+    pub(crate) fn indices(len: u128) -> Self {
+        Self {
+            first: 0,
+            step: 1,
+            len,
+            kind: LitKind::default(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> u128 {
+        self.len
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = i128> {
+        let (first, step) = (self.first, self.step);
+        (0..self.len).map(move |i| first.wrapping_add(step.wrapping_mul(i as i128)))
+    }
+
+    /// `.step_by(step)`, keeps the first value and then every `step`-th one.
+    fn step_by(self, step: u128) -> Self {
+        Self {
+            first: self.first,
+            step: self.step.wrapping_mul(step as i128),
+            len: self.len.div_ceil(step),
+            kind: self.kind,
+        }
+    }
+
+    /// `.rev()`
+    fn rev(self) -> Self {
+        if self.len == 0 {
+            return self;
+        }
+        Self {
+            first: self
+                .first
+                .wrapping_add(self.step.wrapping_mul((self.len - 1) as i128)),
+            step: -self.step,
+            len: self.len,
+            kind: self.kind,
+        }
+    }
+}
+
+/// Radix and suffix of the literals the loop variable is replaced with.
+///
+/// ```text
+/// 0..4          -> 0, 1, 2, 3
+/// 0u8..4u8      -> 0u8, 1u8, 2u8, 3u8
+/// 0x00..0x10    -> 0x0, 0x1, ..., 0xf
+/// ```
+#[derive(Clone, Default)]
+pub(crate) struct LitKind {
+    /// `0x`, `0o`, `0b` or nothing for decimal.
+    prefix: &'static str,
+    /// `u8`, `i64`, ... or nothing.
+    suffix: String,
+}
+
+impl LitKind {
+    /// Radix and suffix of a single literal.
+    pub(crate) fn of(lit: &LitInt) -> Self {
+        let repr = lit.to_string();
+        let prefix = ["0x", "0o", "0b"]
+            .into_iter()
+            .find(|prefix| repr.starts_with(prefix))
+            .unwrap_or_default();
+        Self {
+            prefix,
+            suffix: lit.suffix().to_owned(),
+        }
+    }
+
+    /// A decimal literal of type `suffix`.
+    pub(crate) fn suffixed(suffix: &str) -> Self {
+        Self {
+            prefix: "",
+            suffix: suffix.to_owned(),
+        }
+    }
+
+    pub(crate) fn has_suffix(&self) -> bool {
+        !self.suffix.is_empty()
+    }
+
+    /// Rejects a value that doesn't fit in the integer type given by the suffix.
+    pub(crate) fn check(&self, value: i128, span: Span) -> syn::Result<()> {
+        // An unsuffixed literal takes whatever type it is used as, and a value
+        // is never larger than `u128::MAX`.
+        let (min, max) = match int_bounds(&self.suffix) {
+            Some((min, max)) => (min, max.unwrap_or(i128::MAX)),
+            None => (i128::MIN, i128::MAX),
+        };
+        if value < min || value > max {
+            return Err(syn::Error::new(
+                span,
+                format!("{} does not fit in `{}`", value, self.suffix),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn literal(&self, value: i128, span: Span) -> Literal {
+        let digits = match self.prefix {
+            "0x" => format!("{:x}", value.unsigned_abs()),
+            "0o" => format!("{:o}", value.unsigned_abs()),
+            "0b" => format!("{:b}", value.unsigned_abs()),
+            _ => value.unsigned_abs().to_string(),
+        };
+        let sign = if value < 0 { "-" } else { "" };
+        let repr = format!("{}{}{}{}", sign, self.prefix, digits, self.suffix);
+        LitInt::new(&repr, span).token()
+    }
+
+    /// The literal to put in the output. A negative one is parenthesized so that
+    /// it stays a single operand: `-2.pow(2)` would be `-(2.pow(2))`. The
+    /// compiler flattens an invisible group around a literal, so it can't be one.
+    pub(crate) fn token(&self, value: i128, span: Span) -> TokenTree {
+        let literal = TokenTree::from(self.literal(value, span));
+        if value >= 0 {
+            return literal;
+        }
+        let mut group = Group::new(Delimiter::Parenthesis, TokenStream::from(literal));
+        group.set_span(span);
+        TokenTree::from(group)
+    }
+}
+
+// ```rust, ignore
+// seq!(N in -4..4 { ... });
+// seq!(N in 0..2 * 8 { ... });
+// seq!(N in (0..64).step_by(8) { ... });
+// seq!(N in 0..64 ; 8 { ... });
+// seq!(N in (0..8).rev() { ... });
+// ```
+impl Parse for Range {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        // A whole range given by a `$range:expr` metavariable.
+        if let Some((inside, _, _)) = input.cursor().group(Delimiter::None) {
+            if is_range(inside.token_stream()) {
+                let group = match input.parse::<TokenTree>()? {
+                    TokenTree::Group(group) => group,
+                    _ => unreachable!("checked to be a group"),
+                };
+                let mut range = syn::parse2::<Range>(group.stream())?;
+                if input.parse::<Option<Token![;]>>()?.is_some() {
+                    range = range.step_by(parse_step(input)?);
+                }
+                return Ok(range);
+            }
+        }
+
+        // `(-2)..0` is a parenthesized bound, `(0..8).rev()` a parenthesized range.
+        let parenthesized = input
+            .cursor()
+            .group(Delimiter::Parenthesis)
+            .is_some_and(|(inside, _, _)| is_range(inside.token_stream()));
+        if !parenthesized {
+            let mut range = parse_bounds(input)?;
+            if input.parse::<Option<Token![;]>>()?.is_some() {
+                range = range.step_by(parse_step(input)?);
+            }
+            return Ok(range);
+        }
+
+        let content;
+        parenthesized!(content in input);
+        let mut range = parse_bounds(&content)?;
+        if !content.is_empty() {
+            return Err(content.error("expected `)`"));
+        }
+
+        while input.parse::<Option<Token![.]>>()?.is_some() {
+            let method: syn::Ident = input.parse()?;
+            let args;
+            parenthesized!(args in input);
+            range = if method == "step_by" {
+                let step = parse_step(&args)?;
+                if !args.is_empty() {
+                    return Err(args.error("`step_by` takes a single argument"));
+                }
+                range.step_by(step)
+            } else if method == "rev" {
+                if !args.is_empty() {
+                    return Err(args.error("`rev` takes no argument"));
+                }
+                range.rev()
+            } else {
+                return Err(syn::Error::new(
+                    method.span(),
+                    "expected `step_by(..)` or `rev()`",
+                ));
+            };
+        }
+
+        Ok(range)
+    }
+}
+
+/// Whether the tokens of a group hold a range rather than a single bound.
+fn is_range(tokens: TokenStream) -> bool {
+    tokens
+        .into_iter()
+        .any(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == '.'))
+}
+
+/// `start..end` or `start..=end`
+fn parse_bounds(input: syn::parse::ParseStream) -> syn::Result<Range> {
+    let (start, start_kind, start_span) = parse_const(input)?;
+    input.parse::<Token![..]>()?;
+    let inclusive = input.parse::<Option<Token![=]>>()?.is_some();
+    let (end, end_kind, end_span) = parse_const(input)?;
+
+    let suffix = match (start_kind.suffix.as_str(), end_kind.suffix.as_str()) {
+        (start, end) if !start.is_empty() && !end.is_empty() && start != end => {
+            return Err(syn::Error::new(
+                end_span,
+                format!(
+                    "mismatched suffixes, the range starts at a `{}` and ends at a `{}`",
+                    start, end
+                ),
+            ))
+        }
+        ("", suffix) | (suffix, _) => suffix.to_owned(),
+    };
+
+    // `8..0` is a typo more often than an intentionally empty range.
+    let empty_end = if inclusive {
+        end.checked_add(1)
+    } else {
+        Some(end)
+    };
+    if empty_end.is_some_and(|empty_end| empty_end < start) {
+        return Err(syn::Error::new(
+            start_span,
+            format!(
+                "the range `{}..{}{}` ends before it starts, write it in increasing order and add `.rev()` to count down",
+                start,
+                if inclusive { "=" } else { "" },
+                end
+            ),
+        ));
+    }
+
+    // Computed without `end - start` or `end + 1`, which overflow at the
+    // limits of the integer types.
+    let len = match (inclusive, end.abs_diff(start)) {
+        (_, _) if end < start => 0,
+        (true, distance) => distance.saturating_add(1),
+        (false, distance) => distance,
+    };
+    Ok(Range {
+        first: start,
+        step: 1,
+        len,
+        kind: LitKind {
+            suffix,
+            ..start_kind
+        },
+    })
+}
+
+// ```rust, ignore
+// 4
+// -4
+// 0x04u8
+// 2 * 8
+// $n - 1
+// ```
+//
+// A bound or a step is a constant expression, running up to the next `..`,
+// `;`, `,` or to the body. The tokens of a `macro_rules!` metavariable arrive
+// wrapped in an invisible group, which is evaluated like a parenthesized
+// expression.
+fn parse_const(input: syn::parse::ParseStream) -> syn::Result<(i128, LitKind, Span)> {
+    let span = input.span();
+    let mut tokens = TokenStream::new();
+    while !input.is_empty()
+        && !input.peek(Token![..])
+        && !input.peek(Token![;])
+        && !input.peek(Token![,])
+        && !input.peek(syn::token::Brace)
+    {
+        tokens.extend([input.parse::<TokenTree>()?]);
+    }
+    if tokens.is_empty() {
+        return Err(input.error("expected a constant expression"));
+    }
+
+    let mut group = Group::new(Delimiter::None, tokens);
+    group.set_span(span);
+    let evaluated = evaluate(&group, &[])?;
+    match evaluated.value {
+        Const::Int(value) => Ok((value, evaluated.kind, span)),
+        Const::Bool(_) => Err(syn::Error::new(
+            span,
+            "expected an integer, found a boolean",
+        )),
+    }
+}
+
+fn parse_step(input: syn::parse::ParseStream) -> syn::Result<u128> {
+    let (step, _, span) = parse_const(input)?;
+    if step <= 0 {
+        return Err(syn::Error::new(span, "the step must be greater than 0"));
+    }
+    Ok(step as u128)
+}
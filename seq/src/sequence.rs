@@ -0,0 +1,1017 @@
+use crate::binding::{bound_names, count, iterations, Binding, Item, Source, Value};
+use crate::expr::{evaluate, Const, Expr};
+use crate::template::Template;
+use proc_macro2::{TokenStream, TokenTree};
+use syn::{braced, bracketed, parse::Parse, Token};
+
+/// Number of repetitions a `seq!` expands to without `#![limit = N]`.
+const DEFAULT_LIMIT: u128 = 65536;
+
+pub(crate) struct Sequence {
+    pub(crate) bindings: Vec<Binding>,
+    body: proc_macro2::TokenStream,
+    /// `#![limit = N]`
+    limit: u128,
+    /// `#![debug]`, prints the expansion.
+    debug: bool,
+    /// Repetitions expanded so far, counting the ones of nested sections.
+    expanded: std::cell::Cell<u128>,
+    /// Where the header starts.
+    pub(crate) span: proc_macro2::Span,
+}
+
+// ```rust, ignore
+// seq!(N in 0..8 {
+//   ...
+// });
+//
+// seq!(N as hex in 0..16 {
+//   ...
+// });
+//
+// seq!(I in 0..4, J in 0..4 {
+//   ...
+// });
+//
+// seq!((A, B) in zip(0..4, 10..14) {
+//   ...
+// });
+//
+// seq!(i, T in [u8, u16, u32] {
+//   ...
+// });
+//
+// seq!(#![limit = 100000] N in 0..100000 {
+//   ...
+// });
+// ```
+impl Parse for Sequence {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut seq = Self::parse_header(input)?;
+        let content;
+        braced!(content in input);
+        seq.body = content.parse()?;
+        Ok(seq)
+    }
+}
+
+impl Sequence {
+    /// Everything but the body, which is left empty.
+    pub(crate) fn parse_header(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut limit = DEFAULT_LIMIT;
+        let mut debug = false;
+        while input.peek(Token![#]) {
+            input.parse::<Token![#]>()?;
+            input.parse::<Token![!]>()?;
+            let content;
+            bracketed!(content in input);
+            let name: syn::Ident = content.parse()?;
+            if name == "limit" {
+                content.parse::<Token![=]>()?;
+                limit = content.parse::<syn::LitInt>()?.base10_parse()?;
+            } else if name == "debug" {
+                debug = true;
+            } else {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "expected `limit = N` or `debug`",
+                ));
+            }
+            if !content.is_empty() {
+                return Err(content.error("unexpected token"));
+            }
+        }
+
+        let span = input.span();
+        let mut bindings = vec![input.parse::<Binding>()?];
+        while input.parse::<Option<Token![,]>>()?.is_some() {
+            bindings.push(input.parse()?);
+        }
+        Ok(Self {
+            bindings,
+            body: TokenStream::new(),
+            limit,
+            debug,
+            expanded: std::cell::Cell::new(0),
+            span,
+        })
+    }
+}
+
+/// A `#(...)*` repetition section.
+struct Section {
+    body: TokenStream,
+    /// The `,` of `#(...),*`, put between the repetitions.
+    separator: Option<proc_macro2::Punct>,
+    /// The `[K in 0..N]` or `[0..N]` of `#(...)*[0..N]`, repeating the section
+    /// over its own range instead of the ones of the header.
+    range: Option<proc_macro2::Group>,
+    span: proc_macro2::Span,
+}
+
+impl Sequence {
+    // ```rust, ignore
+    // seq!(N in 0..4 {
+    //     fn sum(#(x~N: u32),*) -> u32 {
+    //         0 #(+ x~N)*
+    //     }
+    // });
+    // ```
+    //
+    // A body without any repetition section is repeated as a whole. Once it
+    // has one, only the sections are repeated and the tokens around them are
+    // kept once, so a loop variable outside of the sections has no value and
+    // is an error.
+    pub(crate) fn expand(&mut self) -> syn::Result<TokenStream> {
+        let mut stray = None;
+        let (tokens, found) = self.replace_repetition_section(self.body.clone(), &mut stray)?;
+        if !found {
+            self.reserve(count(&self.bindings), self.span)?;
+            return self.repeat(&self.body, &[], &iterations(&self.bindings), None);
+        }
+        if let Some(ident) = stray {
+            return Err(syn::Error::new(
+                ident.span(),
+                format!(
+                    "`{}` is used outside of the `#(...)*` sections, only the sections are repeated when the body has any",
+                    ident
+                ),
+            ));
+        }
+        Ok(tokens)
+    }
+
+    /// Prints the expansion to the standard error with `#![debug]`, the
+    /// compiler showing it while building.
+    pub(crate) fn print_expansion(&self, name: &str, tokens: &TokenStream) {
+        if let Some(report) = self.debug_report(name, tokens) {
+            eprintln!("{}", report);
+        }
+    }
+
+    /// What `#![debug]` prints, nothing without it.
+    pub(crate) fn debug_report(&self, name: &str, tokens: &TokenStream) -> Option<String> {
+        self.debug
+            .then(|| format!("{} expanded to:\n{}", name, tokens))
+    }
+
+    pub(crate) fn is_header_var(&self, ident: &proc_macro2::Ident) -> bool {
+        self.bindings
+            .iter()
+            .flat_map(Binding::vars)
+            .any(|var| var.name == *ident)
+    }
+
+    /// Counts `repetitions` more repetitions, failing once there are more than
+    /// the limit, before they are expanded.
+    pub(crate) fn reserve(&self, repetitions: u128, span: proc_macro2::Span) -> syn::Result<()> {
+        let expanded = self.expanded.get().saturating_add(repetitions);
+        if expanded > self.limit {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "this expands to {} repetitions, more than the limit of {}; raise it with `#![limit = {}]` if this is intended",
+                    expanded, self.limit, expanded
+                ),
+            ));
+        }
+        self.expanded.set(expanded);
+        Ok(())
+    }
+
+    /// Concatenates one copy of `body` per iteration, with `separator` between
+    /// them. `outer` are the values of the enclosing sections.
+    fn repeat(
+        &self,
+        body: &TokenStream,
+        outer: &[Value],
+        iterations: &[Vec<Value>],
+        separator: Option<&proc_macro2::Punct>,
+    ) -> syn::Result<TokenStream> {
+        // Every iteration binds the same variables.
+        let names = outer
+            .iter()
+            .chain(iterations.first().into_iter().flatten())
+            .map(|value| &value.var.name)
+            .collect::<Vec<_>>();
+        let template = Template::compile(body.clone(), &names);
+
+        let mut ts = TokenStream::new();
+        for (i, iteration) in iterations.iter().enumerate() {
+            if let (Some(separator), true) = (separator, i > 0) {
+                ts.extend([TokenTree::from(separator.clone())]);
+            }
+            let env = outer.iter().chain(iteration).copied().collect::<Vec<_>>();
+            let tokens = template
+                .instantiate(self, &env)
+                .map_err(|err| in_iteration(err, &env))?;
+            ts.extend(tokens);
+        }
+        Ok(ts)
+    }
+
+    // ```rust, ignore
+    // #(Irq~N,)*
+    // #(T~N),*
+    // #( #(x~K,)*[K in 0..N] )*
+    // ```
+    //
+    // A section without a range is repeated over the header, a section nested
+    // in another one needs its own range, which may use the variables of the
+    // enclosing sections.
+    fn expand_section(&self, section: Section, outer: &[Value]) -> syn::Result<TokenStream> {
+        let binding =
+            match &section.range {
+                Some(range) => Some(self.sub_range(range, outer)?),
+                None if !outer.is_empty() => return Err(syn::Error::new(
+                    section.span,
+                    "a nested repetition section needs its own range, e.g. `#(...)*[K in 0..N]`",
+                )),
+                None => None,
+            };
+        let bindings = match &binding {
+            Some(binding) => std::slice::from_ref(binding),
+            None => &self.bindings,
+        };
+        self.reserve(count(bindings), section.span)?;
+        let iterations = iterations(bindings);
+        self.repeat(
+            &section.body,
+            outer,
+            &iterations,
+            section.separator.as_ref(),
+        )
+    }
+
+    /// `[K in 1..N]`, `[K in [a, b]]`, or `[1..N]` which rebinds the single
+    /// variable of the header.
+    fn sub_range(&self, range: &proc_macro2::Group, outer: &[Value]) -> syn::Result<Binding> {
+        let stream = self.replace_number(range.stream(), outer, false)?;
+        let header_var = match self.bindings.as_slice() {
+            [binding] if binding.vars().len() == 1 => Some(&binding.vars()[0]),
+            _ => None,
+        };
+        let span = range.span();
+        let parser = |input: syn::parse::ParseStream| {
+            // A range never starts with an identifier, `(A, B) in zip(..)` does.
+            let names_vars = input.peek(syn::Ident)
+                || matches!(
+                    input.cursor().group(proc_macro2::Delimiter::Parenthesis),
+                    Some((inside, _, _)) if inside.ident().is_some()
+                );
+            if names_vars {
+                return input.parse::<Binding>();
+            }
+            let source = input.parse::<Source>()?;
+            match header_var {
+                Some(var) => Ok(Binding::new(var.clone(), source)),
+                None => Err(syn::Error::new(
+                    span,
+                    "the header binds several variables, name the one the section repeats over, e.g. `[K in 0..4]`",
+                )),
+            }
+        };
+        syn::parse::Parser::parse2(parser, stream)
+    }
+
+    /// #[derive(Copy, Clone, PartialEq, Debug)]
+    /// enum Interrupt {
+    ///   #(Irq~N,)*
+    /// }
+    ///
+    /// `stray` is the first loop variable found outside of the sections.
+    fn replace_repetition_section(
+        &self,
+        body: TokenStream,
+        stray: &mut Option<proc_macro2::Ident>,
+    ) -> syn::Result<(TokenStream, bool)> {
+        let mut output_stream = TokenStream::new();
+        let mut repetition_found = false;
+        let mut token_iter = body.into_iter();
+        while let Some(token) = token_iter.next() {
+            let output_token: TokenTree = match token {
+                // A possible repeated section '#'
+                TokenTree::Punct(ref punct) if punct.as_char() == '#' => {
+                    match parse_section(&mut token_iter, token.span()) {
+                        Some(section) => {
+                            repetition_found = true;
+                            let stream = self.expand_section(section, &[])?;
+                            let mut group =
+                                proc_macro2::Group::new(proc_macro2::Delimiter::None, stream);
+                            group.set_span(token.span());
+                            TokenTree::from(group)
+                        }
+                        None => match token_iter.clone().next() {
+                            // #N
+                            Some(TokenTree::Ident(ident)) if self.is_header_var(&ident) => {
+                                token_iter.next(); // Consume ident
+                                TokenTree::from(ident)
+                            }
+                            _ => token,
+                        },
+                    }
+                }
+                // $N
+                TokenTree::Punct(ref punct) if punct.as_char() == '$' => {
+                    match token_iter.clone().next() {
+                        Some(TokenTree::Ident(ident)) => {
+                            token_iter.next(); // Consume ident
+                            output_stream.extend([token]);
+                            TokenTree::from(ident)
+                        }
+                        _ => token,
+                    }
+                }
+                // The repetition sections of a nested `seq!` belong to it, the
+                // loop variables are still replaced inside it by `replace_number`.
+                TokenTree::Ident(ref ident) if ident == "seq" => match look_ahead2(&token_iter) {
+                    (Some(TokenTree::Punct(bang)), Some(TokenTree::Group(group)))
+                        if bang.as_char() == '!' =>
+                    {
+                        token_iter.next(); // Consume '!'
+                        token_iter.next(); // Consume (...)
+                        output_stream.extend([token, TokenTree::from(bang)]);
+                        TokenTree::from(group)
+                    }
+                    _ => token,
+                },
+                // What a nested `seq!` left of a chain, such as `ROW~{1}`.
+                TokenTree::Ident(ref ident)
+                    if starts_paste(&token_iter)
+                        && !paste_mentions(ident, &token_iter, &|ident| {
+                            self.is_header_var(ident)
+                        }) =>
+                {
+                    TokenTree::from(paste(ident, &mut token_iter, &[])?)
+                }
+                TokenTree::Ident(ref ident) => {
+                    if self.is_header_var(ident) && stray.is_none() {
+                        *stray = Some(ident.clone());
+                    }
+                    token
+                }
+                // Expand content of (), {}, []
+                TokenTree::Group(ref group) => {
+                    let del = group.delimiter();
+                    let (stream, found) = self.replace_repetition_section(group.stream(), stray)?;
+                    repetition_found |= found;
+                    let mut group = proc_macro2::Group::new(del, stream);
+                    group.set_span(token.span());
+                    TokenTree::from(group)
+                }
+                _ => token,
+            };
+            output_stream.extend(TokenStream::from(output_token));
+        }
+        Ok((output_stream, repetition_found))
+    }
+
+    /// fn f~N () -> u64 {
+    ///     N * 2
+    /// }
+    ///
+    /// const REG_~N:02: u8 = N;
+    ///
+    /// The sections found when `sections` is set are expanded with `env` as
+    /// their outer values. Unset inside a nested `seq!`, where the pastes and
+    /// `#{...}` that don't use any variable of `env` are left to it, and the
+    /// pastes that do only have their segments from `env` replaced.
+    ///
+    /// Every identifier equal to a loop variable is replaced, including field
+    /// accesses such as `self.N`, except:
+    /// - `#N`, which stands for the identifier `N` itself,
+    /// - `$N`, a metavariable of a `macro_rules!` defined in the body,
+    /// - inside a nested `seq!`, the variables that it binds again, which are
+    ///   left for it to replace. The others are replaced in its header too.
+    pub(crate) fn replace_number(
+        &self,
+        body: TokenStream,
+        env: &[Value],
+        sections: bool,
+    ) -> syn::Result<TokenStream> {
+        let mut output_stream = TokenStream::new();
+        let mut token_iter = body.into_iter();
+
+        while let Some(token) = token_iter.next() {
+            let output_token = match token {
+                // Reg~N~Ctrl, f~{N + 1}, N~_suffix
+                TokenTree::Ident(ref ident) if starts_paste(&token_iter) && sections => {
+                    TokenTree::from(paste(ident, &mut token_iter, env)?)
+                }
+                // In a nested `seq!`, X~I~J -> X~{0}~J
+                TokenTree::Ident(ref ident)
+                    if starts_paste(&token_iter)
+                        && paste_mentions(ident, &token_iter, &|ident| {
+                            lookup(env, ident).is_some()
+                        }) =>
+                {
+                    output_stream.extend(self.partial_paste(ident, &mut token_iter, env)?);
+                    continue;
+                }
+                // The sections of a nested `seq!` are left to it, and so are the
+                // variables it binds again.
+                TokenTree::Ident(ref ident) if ident == "seq" => match look_ahead2(&token_iter) {
+                    (Some(TokenTree::Punct(bang)), Some(TokenTree::Group(group)))
+                        if bang.as_char() == '!' =>
+                    {
+                        token_iter.next(); // Consume '!'
+                        token_iter.next(); // Consume (...)
+                        let rebound = bound_names(group.stream());
+                        let env = env
+                            .iter()
+                            .filter(|value| !rebound.contains(&value.var.name))
+                            .copied()
+                            .collect::<Vec<_>>();
+                        let stream = self.replace_number(group.stream(), &env, false)?;
+                        let mut inner = proc_macro2::Group::new(group.delimiter(), stream);
+                        inner.set_span(group.span());
+                        output_stream.extend([token, TokenTree::from(bang)]);
+                        TokenTree::from(inner)
+                    }
+                    _ => token,
+                },
+                // N
+                TokenTree::Ident(ref ident) => match lookup(env, ident) {
+                    Some(value) => substitute(value.item, token.span()),
+                    None => token,
+                },
+                // 'a~N
+                TokenTree::Punct(ref punct)
+                    if punct.as_char() == '\''
+                        && punct.spacing() == proc_macro2::Spacing::Joint =>
+                {
+                    match token_iter.clone().next() {
+                        Some(TokenTree::Ident(ident)) => {
+                            let mut after_ident = token_iter.clone();
+                            after_ident.next();
+                            if starts_paste(&after_ident) && sections {
+                                token_iter.next(); // Consume ident
+                                output_stream.extend([token]);
+                                TokenTree::from(paste(&ident, &mut token_iter, env)?)
+                            } else if starts_paste(&after_ident)
+                                && paste_mentions(&ident, &after_ident, &|ident| {
+                                    lookup(env, ident).is_some()
+                                })
+                            {
+                                token_iter.next(); // Consume ident
+                                output_stream.extend([token]);
+                                output_stream.extend(self.partial_paste(
+                                    &ident,
+                                    &mut token_iter,
+                                    env,
+                                )?);
+                                continue;
+                            } else {
+                                token
+                            }
+                        }
+                        _ => token,
+                    }
+                }
+                // #( #(x~K,)*[K in 0..N] )*, #{N * 2 + 1}, #N
+                TokenTree::Punct(ref punct) if punct.as_char() == '#' => {
+                    let section = if sections {
+                        parse_section(&mut token_iter, token.span())
+                    } else {
+                        None
+                    };
+                    match (section, token_iter.clone().next()) {
+                        (Some(section), _) => {
+                            let stream = self.expand_section(section, env)?;
+                            let mut group =
+                                proc_macro2::Group::new(proc_macro2::Delimiter::None, stream);
+                            group.set_span(token.span());
+                            TokenTree::from(group)
+                        }
+                        (None, Some(TokenTree::Group(group)))
+                            if group.delimiter() == proc_macro2::Delimiter::Brace
+                                && (sections
+                                    || mentions(group.stream(), &|ident| {
+                                        lookup(env, ident).is_some()
+                                    })) =>
+                        {
+                            token_iter.next(); // Consume {...}
+                            evaluate(&group, env)?.to_token(group.span())?
+                        }
+                        // #N
+                        (None, Some(TokenTree::Ident(ident))) if lookup(env, &ident).is_some() => {
+                            token_iter.next(); // Consume ident
+                            TokenTree::from(ident)
+                        }
+                        _ => token,
+                    }
+                }
+                // $N, a `macro_rules!` metavariable
+                TokenTree::Punct(ref punct) if punct.as_char() == '$' => {
+                    match token_iter.clone().next() {
+                        Some(TokenTree::Ident(ident)) => {
+                            token_iter.next(); // Consume ident
+                            output_stream.extend([token]);
+                            TokenTree::from(ident)
+                        }
+                        _ => token,
+                    }
+                }
+                // Expand content of (), {}, []
+                TokenTree::Group(ref group) => {
+                    let del = group.delimiter();
+                    let stream = self.replace_number(group.stream(), env, sections)?;
+                    let mut group = proc_macro2::Group::new(del, stream);
+                    group.set_span(token.span());
+                    TokenTree::from(group)
+                }
+                _ => token,
+            };
+            output_stream.extend(TokenStream::from(output_token));
+        }
+
+        Ok(output_stream)
+    }
+}
+
+impl Sequence {
+    // ```rust, ignore
+    // seq!(I in 0..2 {
+    //     seq!(J in 0..2 { #( const X~I~J: u32 = 0; )* });
+    // });
+    //
+    // seq!(J in 0..2 { #( const X~{0}~J: u32 = 0; )* });
+    // seq!(J in 0..2 { #( const X~{1}~J: u32 = 0; )* });
+    // ```
+    //
+    // In the body of a nested `seq!`, only the segments of a paste chain bound
+    // by `env` are replaced, the chain being pasted by the nested `seq!` which
+    // knows the other variables. The identifiers are kept as they are since
+    // they may be its variables, the values become `{...}` segments.
+    fn partial_paste(
+        &self,
+        first: &proc_macro2::Ident,
+        token_iter: &mut proc_macro2::token_stream::IntoIter,
+        env: &[Value],
+    ) -> syn::Result<TokenStream> {
+        let mut segments = Vec::new();
+        let mut segment = TokenTree::from(first.clone());
+        loop {
+            let span = segment.span();
+            match &segment {
+                TokenTree::Ident(ident) if lookup(env, ident).is_some() => {
+                    let width = paste_width(token_iter);
+                    let mut text = String::new();
+                    Segment::Var(ident.clone()).push(&mut text, width, env)?;
+                    segments.extend(text_segments(&text, span));
+                }
+                TokenTree::Group(group) => {
+                    let stream = self.replace_number(group.stream(), env, false)?;
+                    let mut copy = proc_macro2::Group::new(group.delimiter(), stream);
+                    copy.set_span(group.span());
+                    segments.push(keep_width(TokenTree::from(copy), token_iter));
+                }
+                _ => segments.push(keep_width(segment, token_iter)),
+            }
+            if !starts_paste(token_iter) {
+                break;
+            }
+            token_iter.next(); // Consume '~'
+            segment = token_iter.next().unwrap();
+        }
+
+        let mut output = TokenStream::new();
+        for (i, segment) in segments.into_iter().enumerate() {
+            if i > 0 {
+                output.extend([TokenTree::from(proc_macro2::Punct::new(
+                    '~',
+                    proc_macro2::Spacing::Alone,
+                ))]);
+            }
+            output.extend(segment);
+        }
+        Ok(output)
+    }
+}
+
+/// The segments a pasted text is written back as: `X0` as is, `07` as
+/// `{7}:02` and `1F` as `{1}~F`, since a segment can't start with a digit.
+fn text_segments(text: &str, span: proc_macro2::Span) -> Vec<TokenStream> {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (number, rest) = text.split_at(digits);
+    let mut segments = Vec::new();
+    if !number.is_empty() {
+        let mut literal = proc_macro2::Literal::u128_unsuffixed(number.parse().unwrap());
+        literal.set_span(span);
+        let mut group = proc_macro2::Group::new(
+            proc_macro2::Delimiter::Brace,
+            TokenStream::from(TokenTree::from(literal)),
+        );
+        group.set_span(span);
+        let mut segment = TokenStream::from(TokenTree::from(group));
+        if number.len() > 1 && number.starts_with('0') {
+            let mut colon = proc_macro2::Punct::new(':', proc_macro2::Spacing::Alone);
+            colon.set_span(span);
+            let width = format!("0{}", number.len())
+                .parse::<proc_macro2::Literal>()
+                .unwrap();
+            segment.extend([TokenTree::from(colon), TokenTree::from(width)]);
+        }
+        segments.push(segment);
+    }
+    if !rest.is_empty() {
+        let ident = proc_macro2::Ident::new(rest, span);
+        segments.push(TokenStream::from(TokenTree::from(ident)));
+    }
+    segments
+}
+
+/// A kept segment with the `:02` width that may follow it.
+fn keep_width(
+    segment: TokenTree,
+    token_iter: &mut proc_macro2::token_stream::IntoIter,
+) -> TokenStream {
+    let mut tokens = TokenStream::from(segment);
+    if let (Some(TokenTree::Punct(colon)), Some(TokenTree::Literal(_))) = look_ahead2(token_iter) {
+        if colon.as_char() == ':' && colon.spacing() == proc_macro2::Spacing::Alone {
+            tokens.extend(token_iter.take(2));
+        }
+    }
+    tokens
+}
+
+/// Tells in which iteration the errors raised while expanding one happened.
+///
+/// ```text
+/// attempt to compute `255 + 1`, which would overflow (in iteration `N = 255u8`)
+/// ```
+///
+/// The errors of a nested section already tell the values of the outer
+/// variables too, and are kept as they are.
+///
+/// Only the errors raised by `seq!` itself can be annotated. The ones rustc
+/// reports in the expanded code point at the template token and can't be given
+/// a note on stable Rust, `#![debug]` shows the code they are reported in.
+pub(crate) fn in_iteration(err: syn::Error, env: &[Value]) -> syn::Error {
+    let values = env
+        .iter()
+        .map(|value| {
+            let value_repr = match value.item {
+                Item::Int(number, kind) => kind
+                    .literal(number, proc_macro2::Span::call_site())
+                    .to_string(),
+                Item::Tokens(tokens) => tokens.to_string(),
+            };
+            format!("{} = {}", value.var.name, value_repr)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    err.into_iter()
+        .map(|err| {
+            let message = err.to_string();
+            if message.ends_with("`)") && message.contains(" (in iteration `") {
+                return err;
+            }
+            syn::Error::new(
+                err.span(),
+                format!("{} (in iteration `{}`)", message, values),
+            )
+        })
+        .reduce(|mut all, err| {
+            all.combine(err);
+            all
+        })
+        .expect("an error has at least one message")
+}
+
+/// Consumes the `(...)*`, `(...),*` or `(...)*[0..N]` that may follow a `#`.
+fn parse_section(
+    token_iter: &mut proc_macro2::token_stream::IntoIter,
+    span: proc_macro2::Span,
+) -> Option<Section> {
+    let mut peek = token_iter.clone();
+    let body = match peek.next() {
+        Some(TokenTree::Group(group))
+            if group.delimiter() == proc_macro2::Delimiter::Parenthesis =>
+        {
+            group.stream()
+        }
+        _ => return None,
+    };
+    let separator = match peek.next() {
+        Some(TokenTree::Punct(star)) if star.as_char() == '*' => None,
+        Some(TokenTree::Punct(separator)) => match peek.next() {
+            Some(TokenTree::Punct(star)) if star.as_char() == '*' => {
+                let mut alone =
+                    proc_macro2::Punct::new(separator.as_char(), proc_macro2::Spacing::Alone);
+                alone.set_span(separator.span());
+                Some(alone)
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let range = match peek.clone().next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == proc_macro2::Delimiter::Bracket => {
+            peek.next();
+            Some(group)
+        }
+        _ => None,
+    };
+    *token_iter = peek;
+    Some(Section {
+        body,
+        separator,
+        range,
+        span,
+    })
+}
+
+/// Variables of a later binding shadow the ones of an earlier binding.
+pub(crate) fn lookup<'a>(
+    env: &'a [Value<'a>],
+    ident: &proc_macro2::Ident,
+) -> Option<&'a Value<'a>> {
+    env.iter().rev().find(|value| value.var.name == *ident)
+}
+
+/// A number is written like the bounds of its range, the tokens of a list
+/// element are kept as they are.
+pub(crate) fn substitute(item: Item, span: proc_macro2::Span) -> TokenTree {
+    match item {
+        Item::Int(value, kind) => kind.token(value, span),
+        Item::Tokens(tokens) => {
+            let mut trees = tokens.clone().into_iter();
+            match (trees.next(), trees.next()) {
+                (Some(tree), None) => tree,
+                _ => {
+                    let mut group =
+                        proc_macro2::Group::new(proc_macro2::Delimiter::None, tokens.clone());
+                    group.set_span(span);
+                    TokenTree::from(group)
+                }
+            }
+        }
+    }
+}
+
+/// Whether a variable, for which `is_var` holds, appears in `stream`.
+fn mentions(stream: TokenStream, is_var: &dyn Fn(&proc_macro2::Ident) -> bool) -> bool {
+    stream.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => is_var(&ident),
+        TokenTree::Group(group) => mentions(group.stream(), is_var),
+        _ => false,
+    })
+}
+
+/// Whether a variable appears in the paste chain starting with `first`,
+/// `token_iter` being right after it.
+fn paste_mentions(
+    first: &proc_macro2::Ident,
+    token_iter: &proc_macro2::token_stream::IntoIter,
+    is_var: &dyn Fn(&proc_macro2::Ident) -> bool,
+) -> bool {
+    let mut token_iter = token_iter.clone();
+    let mut found = is_var(first);
+    while starts_paste(&token_iter) {
+        token_iter.next(); // Skip '~'
+        let segment = token_iter.next().unwrap();
+        found |= mentions(TokenStream::from(segment), is_var);
+    }
+    found
+}
+
+/// Whether the next tokens are `~ident` or `~{...}`.
+fn starts_paste(token_iter: &proc_macro2::token_stream::IntoIter) -> bool {
+    match look_ahead2(token_iter) {
+        (Some(TokenTree::Punct(punct)), Some(TokenTree::Ident(_))) => punct.as_char() == '~',
+        (Some(TokenTree::Punct(punct)), Some(TokenTree::Group(group))) => {
+            punct.as_char() == '~' && group.delimiter() == proc_macro2::Delimiter::Brace
+        }
+        _ => false,
+    }
+}
+
+// ```rust, ignore
+// f~N             -> f7
+// Reg~N~Ctrl      -> Reg7Ctrl
+// f~{N + 1}       -> f8
+// REG_~N:02       -> REG_07
+// Get~Reg~N:lower -> getreg7
+// ```
+//
+// Every segment joined by `~` is either an identifier, kept as is, a loop
+// variable or a `{...}` expression, replaced by its value. A trailing `:upper`
+// or `:lower` changes the case of the whole identifier.
+fn paste(
+    first: &proc_macro2::Ident,
+    token_iter: &mut proc_macro2::token_stream::IntoIter,
+    env: &[Value],
+) -> syn::Result<proc_macro2::Ident> {
+    Paste::parse(first, token_iter, &|ident| lookup(env, ident).is_some())?.expand(env)
+}
+
+/// A paste chain split once into its segments, so that a template pastes it
+/// in every iteration without reading its tokens again.
+pub(crate) struct Paste {
+    first: proc_macro2::Ident,
+    /// With the number of digits their value is zero-padded to.
+    segments: Vec<(Segment, usize)>,
+    case: Option<Case>,
+}
+
+enum Segment {
+    /// An identifier that isn't a loop variable.
+    Text(String),
+    Var(proc_macro2::Ident),
+    /// `{...}`
+    Expr(Expr, proc_macro2::Span),
+}
+
+/// `:upper` or `:lower`
+#[derive(Clone, Copy)]
+enum Case {
+    Upper,
+    Lower,
+}
+
+impl Paste {
+    /// `token_iter` is right after `first` and is left after the chain. The
+    /// identifiers for which `is_var` holds are the loop variables.
+    pub(crate) fn parse(
+        first: &proc_macro2::Ident,
+        token_iter: &mut proc_macro2::token_stream::IntoIter,
+        is_var: &dyn Fn(&proc_macro2::Ident) -> bool,
+    ) -> syn::Result<Self> {
+        let mut segments = vec![Segment::parse(
+            TokenTree::from(first.clone()),
+            token_iter,
+            is_var,
+        )?];
+        while starts_paste(token_iter) {
+            token_iter.next(); // Consume '~'
+            let segment = token_iter.next().unwrap();
+            segments.push(Segment::parse(segment, token_iter, is_var)?);
+        }
+
+        let mut case = None;
+        if let (Some(TokenTree::Punct(colon)), Some(TokenTree::Ident(ident))) =
+            look_ahead2(token_iter)
+        {
+            if colon.as_char() == ':' && colon.spacing() == proc_macro2::Spacing::Alone {
+                if ident == "upper" {
+                    case = Some(Case::Upper);
+                } else if ident == "lower" {
+                    case = Some(Case::Lower);
+                }
+                if case.is_some() {
+                    token_iter.next();
+                    token_iter.next();
+                }
+            }
+        }
+
+        Ok(Self {
+            first: first.clone(),
+            segments,
+            case,
+        })
+    }
+
+    pub(crate) fn expand(&self, env: &[Value]) -> syn::Result<proc_macro2::Ident> {
+        let mut pasted = String::new();
+        for (segment, width) in &self.segments {
+            segment.push(&mut pasted, *width, env)?;
+        }
+        match self.case {
+            Some(Case::Upper) => pasted = pasted.to_uppercase(),
+            Some(Case::Lower) => pasted = pasted.to_lowercase(),
+            None => {}
+        }
+
+        let mut chars = pasted.chars();
+        let valid = matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+            && pasted != "_";
+        if !valid {
+            return Err(syn::Error::new(
+                self.first.span(),
+                format!("`{}` is not a valid identifier", pasted),
+            ));
+        }
+        Ok(proc_macro2::Ident::new(&pasted, self.first.span()))
+    }
+}
+
+impl Segment {
+    /// A segment with the `:02` width that may follow a value.
+    fn parse(
+        segment: TokenTree,
+        token_iter: &mut proc_macro2::token_stream::IntoIter,
+        is_var: &dyn Fn(&proc_macro2::Ident) -> bool,
+    ) -> syn::Result<(Self, usize)> {
+        Ok(match segment {
+            TokenTree::Ident(ident) if is_var(&ident) => {
+                (Segment::Var(ident), paste_width(token_iter))
+            }
+            TokenTree::Ident(ident) => (Segment::Text(ident.to_string()), 0),
+            TokenTree::Group(group) => (
+                Segment::Expr(Expr::parse(&group)?, group.span()),
+                paste_width(token_iter),
+            ),
+            other => unreachable!("not a paste segment: {}", other),
+        })
+    }
+
+    /// Appends the segment as it is written in this iteration.
+    fn push(&self, pasted: &mut String, width: usize, env: &[Value]) -> syn::Result<()> {
+        let (value, hex, span) = match self {
+            Segment::Text(text) => {
+                pasted.push_str(text);
+                return Ok(());
+            }
+            Segment::Var(ident) => match lookup(env, ident) {
+                Some(Value {
+                    var,
+                    item: Item::Int(value, _),
+                }) => (*value, var.paste_hex, ident.span()),
+                Some(Value {
+                    item: Item::Tokens(tokens),
+                    ..
+                }) => {
+                    let mut trees = (*tokens).clone().into_iter();
+                    return match (trees.next(), trees.next()) {
+                        (Some(TokenTree::Ident(_)), None) if width > 0 => Err(syn::Error::new(
+                            ident.span(),
+                            format!("cannot zero-pad `{}`, it is not a number", tokens),
+                        )),
+                        (Some(TokenTree::Ident(value)), None) => {
+                            pasted.push_str(&value.to_string());
+                            Ok(())
+                        }
+                        _ => Err(syn::Error::new(
+                            ident.span(),
+                            format!("cannot paste `{}` into an identifier", tokens),
+                        )),
+                    };
+                }
+                None => {
+                    pasted.push_str(&ident.to_string());
+                    return Ok(());
+                }
+            },
+            Segment::Expr(expr, span) => {
+                let evaluated = expr.evaluate(env)?;
+                match evaluated.value {
+                    Const::Int(value) => (value, evaluated.paste_hex, *span),
+                    Const::Bool(_) => {
+                        return Err(syn::Error::new(
+                            *span,
+                            "cannot paste a boolean into an identifier",
+                        ))
+                    }
+                }
+            }
+        };
+
+        if value < 0 {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "cannot paste the negative number {} into an identifier",
+                    value
+                ),
+            ));
+        }
+        if hex {
+            pasted.push_str(&format!("{:0width$X}", value, width = width));
+        } else {
+            pasted.push_str(&format!("{:0width$}", value, width = width));
+        }
+        Ok(())
+    }
+}
+
+/// Consumes the `:02` that may follow `~N`, giving the number of digits the
+/// pasted value is zero-padded to.
+fn paste_width(token_iter: &mut proc_macro2::token_stream::IntoIter) -> usize {
+    match look_ahead2(token_iter) {
+        (Some(TokenTree::Punct(colon)), Some(TokenTree::Literal(width)))
+            if colon.as_char() == ':' && colon.spacing() == proc_macro2::Spacing::Alone =>
+        {
+            let repr = width.to_string();
+            if !repr.starts_with('0')
+                || repr.len() < 2
+                || !repr.bytes().all(|byte| byte.is_ascii_digit())
+            {
+                return 0;
+            }
+            token_iter.next(); // Consume ':'
+            token_iter.next(); // Consume width
+            repr[1..].parse().unwrap_or_default()
+        }
+        _ => 0,
+    }
+}
+
+fn look_ahead2(
+    token_iter: &proc_macro2::token_stream::IntoIter,
+) -> (Option<TokenTree>, Option<TokenTree>) {
+    let mut peek = token_iter.clone();
+    (peek.next(), peek.next())
+}
@@ -0,0 +1,268 @@
+use crate::binding::Value;
+use crate::expr::Expr;
+use crate::sequence::{lookup, substitute, Paste, Sequence};
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// A body split once into the tokens that are the same in every iteration and
+/// the ones that depend on the loop variables, so that repeating it only walks
+/// the latter. Paste chains and `#{...}` are parsed here too, an iteration
+/// only computes their values.
+///
+/// ```text
+/// fn f~N() -> u64 { N * 2 }
+///
+/// Paste(f~N) Static(()) Static(-> u64) Group({}, [Var(N), Static(* 2)])
+/// ```
+pub(crate) struct Template {
+    chunks: Vec<Chunk>,
+}
+
+enum Chunk {
+    /// Copied as is.
+    Static(TokenStream),
+    /// A group with some dynamic tokens inside.
+    Group(Group, Vec<Chunk>),
+    /// A lone loop variable, replaced by its value directly.
+    Var(Ident),
+    /// A paste chain, after the `'` of a lifetime if any.
+    Paste(Option<Punct>, Paste),
+    /// `#{...}`
+    Expr(Expr, Span),
+    /// A `#N`, a `#(...)*` section, a nested `seq!` or a paste chain or
+    /// `#{...}` that can't be parsed, handed to `replace_number` whole.
+    Dynamic(TokenStream),
+}
+
+impl Template {
+    /// `names` are the loop variables that the body will be expanded with.
+    pub(crate) fn compile(body: TokenStream, names: &[&Ident]) -> Self {
+        Self {
+            chunks: compile(body, names),
+        }
+    }
+
+    pub(crate) fn instantiate(&self, seq: &Sequence, env: &[Value]) -> syn::Result<TokenStream> {
+        instantiate(&self.chunks, seq, env)
+    }
+}
+
+fn instantiate(chunks: &[Chunk], seq: &Sequence, env: &[Value]) -> syn::Result<TokenStream> {
+    let mut output = TokenStream::new();
+    for chunk in chunks {
+        match chunk {
+            Chunk::Static(tokens) => output.extend(tokens.clone()),
+            Chunk::Group(group, chunks) => {
+                let mut copy = Group::new(group.delimiter(), instantiate(chunks, seq, env)?);
+                copy.set_span(group.span());
+                output.extend([TokenTree::from(copy)]);
+            }
+            Chunk::Var(ident) => match lookup(env, ident) {
+                Some(value) => output.extend([substitute(value.item, ident.span())]),
+                None => output.extend([TokenTree::from(ident.clone())]),
+            },
+            Chunk::Paste(quote, paste) => {
+                output.extend(quote.clone().map(TokenTree::from));
+                output.extend([TokenTree::from(paste.expand(env)?)]);
+            }
+            Chunk::Expr(expr, span) => output.extend([expr.evaluate(env)?.to_token(*span)?]),
+            Chunk::Dynamic(tokens) => {
+                output.extend(seq.replace_number(tokens.clone(), env, true)?)
+            }
+        }
+    }
+    Ok(output)
+}
+
+fn compile(body: TokenStream, names: &[&Ident]) -> Vec<Chunk> {
+    let tokens = body.into_iter().collect::<Vec<_>>();
+    let mut chunks = Vec::new();
+    let mut pending = TokenStream::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let len = match dynamic_len(&tokens, i, names) {
+            Some(len) => len,
+            None => {
+                match &tokens[i] {
+                    TokenTree::Group(group) => {
+                        let inner = compile(group.stream(), names);
+                        if inner.iter().all(|chunk| matches!(chunk, Chunk::Static(_))) {
+                            pending.extend([tokens[i].clone()]);
+                        } else {
+                            flush(&mut pending, &mut chunks);
+                            chunks.push(Chunk::Group(group.clone(), inner));
+                        }
+                    }
+                    // `$N` is a `macro_rules!` metavariable, left as is.
+                    TokenTree::Punct(punct)
+                        if punct.as_char() == '$'
+                            && matches!(tokens.get(i + 1), Some(TokenTree::Ident(_))) =>
+                    {
+                        pending.extend(tokens[i..i + 2].iter().cloned());
+                        i += 1;
+                    }
+                    token => pending.extend([token.clone()]),
+                }
+                i += 1;
+                continue;
+            }
+        };
+        flush(&mut pending, &mut chunks);
+        let (chunk, len) = compile_dynamic(&tokens[i..i + len], names);
+        chunks.push(chunk);
+        i += len;
+    }
+
+    flush(&mut pending, &mut chunks);
+    chunks
+}
+
+/// The chunk of the dynamic construct at the start of `tokens`, with the number
+/// of tokens it takes. The syntax errors of a paste chain or a `#{...}` are
+/// left to `replace_number`, which reports them in the first iteration.
+fn compile_dynamic(tokens: &[TokenTree], names: &[&Ident]) -> (Chunk, usize) {
+    let dynamic = || Chunk::Dynamic(tokens.iter().cloned().collect());
+    let paste = |quote: Option<Punct>, first: &Ident, rest: &[TokenTree]| {
+        let mut token_iter = rest.iter().cloned().collect::<TokenStream>().into_iter();
+        match Paste::parse(first, &mut token_iter, &|ident| names.contains(&ident)) {
+            // What follows the chain, such as `:5` which isn't a width, is left
+            // for the next chunks.
+            Ok(paste) => (
+                Chunk::Paste(quote, paste),
+                tokens.len() - token_iter.count(),
+            ),
+            Err(_) => (dynamic(), tokens.len()),
+        }
+    };
+    match tokens {
+        [TokenTree::Ident(ident)] => (Chunk::Var(ident.clone()), 1),
+        [TokenTree::Ident(first), rest @ ..] if first != "seq" => paste(None, first, rest),
+        [TokenTree::Punct(quote), TokenTree::Ident(first), rest @ ..]
+            if quote.as_char() == '\'' =>
+        {
+            paste(Some(quote.clone()), first, rest)
+        }
+        [TokenTree::Punct(hash), TokenTree::Group(group)]
+            if hash.as_char() == '#' && group.delimiter() == Delimiter::Brace =>
+        {
+            match Expr::parse(group) {
+                Ok(expr) => (Chunk::Expr(expr, group.span()), 2),
+                Err(_) => (dynamic(), 2),
+            }
+        }
+        _ => (dynamic(), tokens.len()),
+    }
+}
+
+fn flush(pending: &mut TokenStream, chunks: &mut Vec<Chunk>) {
+    if !pending.is_empty() {
+        chunks.push(Chunk::Static(std::mem::take(pending)));
+    }
+}
+
+/// The number of tokens of the dynamic construct starting at `i`, if any. It
+/// may take a few more tokens than `replace_number` would replace, which are
+/// then left as they are, but never fewer.
+fn dynamic_len(tokens: &[TokenTree], i: usize, names: &[&Ident]) -> Option<usize> {
+    match &tokens[i] {
+        // seq!(...)
+        TokenTree::Ident(ident) if ident == "seq" && is_punct(tokens.get(i + 1), '!') => {
+            match tokens.get(i + 2) {
+                Some(TokenTree::Group(_)) => Some(3),
+                _ => None,
+            }
+        }
+        // f~N, N
+        TokenTree::Ident(ident) => {
+            let len = paste_len(tokens, i);
+            if len > 1 || names.contains(&ident) {
+                Some(len)
+            } else {
+                None
+            }
+        }
+        // 'a~N
+        TokenTree::Punct(punct)
+            if punct.as_char() == '\''
+                && punct.spacing() == Spacing::Joint
+                && matches!(tokens.get(i + 1), Some(TokenTree::Ident(_))) =>
+        {
+            match paste_len(tokens, i + 1) {
+                1 => None,
+                len => Some(1 + len),
+            }
+        }
+        // #(...)*, #(...),*[..], #{...}, #N, #f~N
+        TokenTree::Punct(punct) if punct.as_char() == '#' => match tokens.get(i + 1) {
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                let mut len = 2;
+                if !is_punct(tokens.get(i + len), '*') {
+                    len += 1; // Separator
+                }
+                if !is_punct(tokens.get(i + len), '*') {
+                    return None;
+                }
+                len += 1;
+                if let Some(TokenTree::Group(range)) = tokens.get(i + len) {
+                    if range.delimiter() == Delimiter::Bracket {
+                        len += 1;
+                    }
+                }
+                Some(len)
+            }
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => Some(2),
+            Some(TokenTree::Ident(_)) => Some(1 + paste_len(tokens, i + 1)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The number of tokens of the paste chain starting with the identifier at
+/// `i`, with its `:02` widths and `:upper` or `:lower`, 1 if there is none.
+fn paste_len(tokens: &[TokenTree], i: usize) -> usize {
+    let mut end = i + 1;
+    end += width_len(tokens, end);
+    while is_punct(tokens.get(end), '~')
+        && match tokens.get(end + 1) {
+            Some(TokenTree::Ident(_)) => true,
+            Some(TokenTree::Group(group)) => group.delimiter() == Delimiter::Brace,
+            _ => false,
+        }
+    {
+        end += 2;
+        end += width_len(tokens, end);
+    }
+    if end > i + 1 && is_punct(tokens.get(end), ':') {
+        if let Some(TokenTree::Ident(case)) = tokens.get(end + 1) {
+            if case == "upper" || case == "lower" {
+                end += 2;
+            }
+        }
+    }
+    // A lone identifier with what looks like a width is not a paste.
+    if end > i + 1
+        && !tokens[i + 1..end]
+            .iter()
+            .any(|token| is_punct(Some(token), '~'))
+    {
+        return 1;
+    }
+    end - i
+}
+
+/// 2 if a `:02` width is at `i`.
+fn width_len(tokens: &[TokenTree], i: usize) -> usize {
+    match (tokens.get(i), tokens.get(i + 1)) {
+        (Some(TokenTree::Punct(colon)), Some(TokenTree::Literal(_)))
+            if colon.as_char() == ':' && colon.spacing() == Spacing::Alone =>
+        {
+            2
+        }
+        _ => 0,
+    }
+}
+
+fn is_punct(token: Option<&TokenTree>, ch: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == ch)
+}
@@ -0,0 +1,52 @@
+// Ranges are not limited to counting up by one from a non-negative number. The
+// bounds can be negative, and like with std ranges a step and a reversal can be
+// chained after a parenthesized range:
+//
+//     seq!(N in -2..2 { ... })                -2, -1, 0, 1
+//     seq!(N in (0..64).step_by(16) { ... })  0, 16, 32, 48
+//     seq!(N in 0..64 ; 16 { ... })           same as above
+//     seq!(N in (0..4).rev() { ... })         3, 2, 1, 0
+//
+// The loop variable is still substituted as an unsuffixed literal, so its type
+// is inferred from where it is used. A negative value stays a single operand,
+// so `N.pow(2)` squares it instead of negating the square, and it can start
+// the range of a nested seq!.
+
+use seq::seq;
+
+const OFFSETS: [i32; 4] = seq!(N in -2..2 { [#(N,)*] });
+
+const BANKS: [u8; 4] = seq!(N in (0..64).step_by(16) { [#(N,)*] });
+
+const BANKS_SHORT: [u8; 4] = seq!(N in 0..64 ; 16 { [#(N,)*] });
+
+const COUNTDOWN: [u8; 4] = seq!(N in (0..4).rev() { [#(N,)*] });
+
+const STRIDED_DOWN: [i64; 3] = seq!(N in (-8..=8).step_by(8).rev() { [#(N,)*] });
+
+const REV_THEN_STEP: [u8; 3] = seq!(N in (0..8).rev().step_by(3) { [#(N,)*] });
+
+const SQUARES: [i32; 2] = seq!(N in -2i32..0 { [#(N.pow(2),)*] });
+
+const SHIFTED_SQUARES: [i32; 2] = seq!(N in 0i32..2 { [#(#{N - 2}.pow(2),)*] });
+
+const TRIANGLE: [usize; 2] = seq!(N in -2..0 { [#(seq!(K in N..0 { 0 #(+ 1)* }),)*] });
+
+seq!(N in (0..24).step_by(8) {
+    fn register~N() -> u32 {
+        N
+    }
+});
+
+fn main() {
+    assert_eq!(OFFSETS, [-2, -1, 0, 1]);
+    assert_eq!(BANKS, [0, 16, 32, 48]);
+    assert_eq!(BANKS_SHORT, BANKS);
+    assert_eq!(COUNTDOWN, [3, 2, 1, 0]);
+    assert_eq!(STRIDED_DOWN, [8, 0, -8]);
+    assert_eq!(REV_THEN_STEP, [7, 4, 1]);
+    assert_eq!(SQUARES, [4, 1]);
+    assert_eq!(SHIFTED_SQUARES, [4, 1]);
+    assert_eq!(TRIANGLE, [2, 1]);
+    assert_eq!(register0() + register8() + register16(), 24);
+}
@@ -0,0 +1,60 @@
+// The literals the loop variable is replaced with keep the suffix and the
+// radix of the range bounds, so a `u8` range produces `u8` values:
+//
+//     seq!(N in 0u8..4u8 { ... })     0u8, 1u8, 2u8, 3u8
+//     seq!(N in 0x00..0x10 { ... })   0x0, 0x1, ..., 0xf
+//
+// Pasted identifiers can be zero-padded with `~N:02`, and `N as hex` pastes
+// the values as uppercase hexadecimal digits:
+//
+//     seq!(N in 0..16 { f~N:02 })             f00, f01, ..., f15
+//     seq!(N as hex in 0..16 { REG_~N:02 })   REG_00, REG_01, ..., REG_0F
+
+use seq::seq;
+
+fn type_name<T>(_: T) -> &'static str {
+    std::any::type_name::<T>()
+}
+
+fn types() -> [&'static str; 4] {
+    seq!(N in 0u8..4u8 {
+        [#(type_name(N),)*]
+    })
+}
+
+seq!(N in 250u8..=255 {
+    // Without the suffix, `N.wrapping_add(1)` would not know which integer
+    // type to use.
+    const WRAPPED: [u8; 6] = [#(N.wrapping_add(1),)*];
+});
+
+seq!(N in 0x00..0x10 {
+    const HEX: [u32; 16] = [#(N,)*];
+});
+
+seq!(N in -0x2i8..0x2 {
+    const SIGNED_HEX: [i8; 4] = [#(N,)*];
+});
+
+seq!(N in 0..12 {
+    #[allow(non_upper_case_globals)]
+    const f~N:02: usize = N;
+});
+
+seq!(N as hex in 8..12 {
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(Debug)]
+    enum Register {
+        #(REG_~N:02,)*
+    }
+});
+
+fn main() {
+    assert_eq!(types(), ["u8"; 4]);
+    assert_eq!(WRAPPED, [251, 252, 253, 254, 255, 0]);
+    assert_eq!(HEX[15], 15);
+    assert_eq!(SIGNED_HEX, [-2, -1, 0, 1]);
+    assert_eq!(f07 + f11, 18);
+    assert_eq!(format!("{:?}", Register::REG_0A), "REG_0A");
+    assert_eq!(format!("{:?}", Register::REG_0B), "REG_0B");
+}
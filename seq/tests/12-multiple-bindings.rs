@@ -0,0 +1,56 @@
+// A header can bind more than one loop variable.
+//
+// Bindings separated by a comma are nested loops, the first one being the
+// outermost:
+//
+//     seq!(I in 0..2, J in 0..3 { ... })   (0, 0), (0, 1), (0, 2), (1, 0), ...
+//
+// Variables bound together with `zip` advance in lockstep and stop with the
+// shortest range:
+//
+//     seq!((A, B) in zip(0..4, 10..14) { ... })   (0, 10), (1, 11), ...
+//
+// A seq! invocation nested in the body of another one keeps its own repetition
+// sections, and can use the variables of the outer invocation.
+
+use seq::seq;
+
+seq!(I in 0..2, J in 0..3 {
+    const CELLS: [(usize, usize); 6] = [#((I, J),)*];
+});
+
+seq!((A, B) in zip(0..4, 10..20) {
+    const PAIRS: [(u8, u8); 4] = [#((A, B),)*];
+});
+
+seq!((A, B, C) in zip(0..3, (0..3).rev(), (0..30).step_by(10)) {
+    const TRIPLES: [(u8, u8, u8); 3] = [#((A, B, C),)*];
+});
+
+seq!(I in 0..3 {
+    #(
+        fn row~I() -> [usize; 4] {
+            seq!(J in 0..4 {
+                [#(I * 10 + J,)*]
+            })
+        }
+    )*
+});
+
+// The outer invocation has no repetition section of its own, so its whole body
+// is repeated, while the inner one repeats its section.
+seq!(I in 1..3 {
+    fn sum~I() -> usize {
+        seq!(J in 0..4 { 0 #(+ I * J)* })
+    }
+});
+
+fn main() {
+    assert_eq!(CELLS, [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]);
+    assert_eq!(PAIRS, [(0, 10), (1, 11), (2, 12), (3, 13)]);
+    assert_eq!(TRIPLES, [(0, 2, 0), (1, 1, 10), (2, 0, 20)]);
+    assert_eq!(row0(), [0, 1, 2, 3]);
+    assert_eq!(row2(), [20, 21, 22, 23]);
+    assert_eq!(sum1(), 6);
+    assert_eq!(sum2(), 12);
+}
@@ -0,0 +1,48 @@
+// `#{...}` is replaced by the value of a constant expression over the loop
+// variables, evaluated when the macro expands:
+//
+//     seq!(N in 0..4 { [#(#{1 << N},)*] })   [1, 2, 4, 8]
+//
+// Integer arithmetic, bitwise operators, shifts and comparisons are supported
+// with the precedence of Rust. Comparisons produce `true` or `false`. The
+// same expressions can be pasted into identifiers with `~{...}`:
+//
+//     seq!(N in 0..3 { fn f~{N + 1}() {} })   f1, f2, f3
+//
+// Any overflow, as well as a value that doesn't fit in the suffix of the range
+// bounds, is reported as a compile error.
+
+use seq::seq;
+
+const POWERS: [u32; 5] = seq!(N in 0..5 { [#(#{1 << N},)*] });
+
+const ODD: [usize; 4] = seq!(N in 0..4 { [#(#{N * 2 + 1},)*] });
+
+const MIXED: [i64; 3] = seq!(N in 1..4 { [#(#{-(N + 1) * (N - 4) / 2 % 5 ^ 0b1},)*] });
+
+// The same expression evaluated by rustc, to check precedence.
+fn mixed(n: i64) -> i64 {
+    -(n + 1) * (n - 4) / 2 % 5 ^ 0b1
+}
+
+const EVEN: [bool; 4] = seq!(N in 0..4 { [#(#{N % 2 == 0 && N != 2},)*] });
+
+seq!(N in 0..3 {
+    fn f~{N + 1}() -> u8 {
+        #{N * 10}
+    }
+});
+
+seq!(N in 0u8..4 {
+    // The result keeps the suffix of the bounds.
+    const SCALED: [u8; 4] = [#(#{N * 60}.wrapping_add(100),)*];
+});
+
+fn main() {
+    assert_eq!(POWERS, [1, 2, 4, 8, 16]);
+    assert_eq!(ODD, [1, 3, 5, 7]);
+    assert_eq!(MIXED, [mixed(1), mixed(2), mixed(3)]);
+    assert_eq!(EVEN, [true, false, false, false]);
+    assert_eq!(f1() + f2() + f3(), 30);
+    assert_eq!(SCALED, [100, 160, 220, 24]);
+}
@@ -0,0 +1,19 @@
+// Overflow while evaluating `#{...}` is reported at the offending operator,
+// and a result that doesn't fit in the suffix of the bounds, `u64` included,
+// at the expression.
+
+use seq::seq;
+
+seq!(N in 126..128 {
+    const X: i128 = #{N * 170141183460469231731687303715884105727};
+});
+
+seq!(N in 0u8..8 {
+    const Y: u8 = #{N * 40};
+});
+
+seq!(N in 0u64..2 {
+    const Z~N: u64 = #{N + u64::MAX};
+});
+
+fn main() {}
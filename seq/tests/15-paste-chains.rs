@@ -0,0 +1,62 @@
+// Identifier pasting is not limited to a prefix followed by the loop variable.
+// Any number of segments can be joined with `~`, each being an identifier, a
+// loop variable or a `{...}` expression:
+//
+//     Reg~N~Ctrl       Reg3Ctrl
+//     m~I~_~J          m1_2
+//     _~N              _3
+//
+// A trailing `:upper` or `:lower` changes the case of the whole identifier,
+// and lifetimes can be pasted too with 'a~N. A chain right after a `#` that
+// is not a section is pasted all the same.
+
+use seq::seq;
+
+seq!(N in 0..3 {
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    enum Register {
+        #(Reg~N~Ctrl,)*
+    }
+});
+
+seq!(I in 0..2, J in 0..2 {
+    #(
+        #[allow(non_upper_case_globals)]
+        const m~I~_~J: usize = I * 2 + J;
+    )*
+});
+
+seq!(N in 0..2 {
+    #(
+        fn Get~Value~N:lower() -> usize {
+            N
+        }
+
+        #[allow(dead_code)]
+        const Limit~{N + 1}:upper: usize = #{N + 1};
+    )*
+});
+
+seq!(N in 0..3 {
+    struct Refs<#('a~N,)*> {
+        #(_~N: &'a~N u8,)*
+    }
+});
+
+seq!(N in 0..2 {
+    const HASHED: [&str; 2] = [#(stringify!(#f~N),)*];
+});
+
+fn main() {
+    assert_eq!(format!("{:?}", Register::Reg2Ctrl), "Reg2Ctrl");
+    assert_eq!(m0_0 + m0_1 + m1_0 + m1_1, 6);
+    assert_eq!(getvalue0() + getvalue1(), 1);
+    assert_eq!(LIMIT1 + LIMIT2, 3);
+
+    let (x, y, z) = (1, 2, 3);
+    let refs = Refs { _0: &x, _1: &y, _2: &z };
+    assert_eq!(*refs._0 + *refs._1 + *refs._2, 6);
+
+    assert_eq!(HASHED, ["# f0", "# f1"]);
+}
@@ -0,0 +1,19 @@
+// A pasted identifier that isn't valid, for instance because it starts with a
+// digit, is reported at the first segment of the paste. Only numbers can be
+// zero-padded.
+
+use seq::seq;
+
+seq!(N in 0..2 {
+    const N~_reg: u8 = 0;
+});
+
+seq!(N in -1..1 {
+    fn f~N() {}
+});
+
+seq!(T in [a, b] {
+    fn f~T:02() {}
+});
+
+fn main() {}
@@ -0,0 +1,50 @@
+// A body can have any number of repetition sections, the tokens around them
+// being kept once. `#(...),*` puts a separator between the repetitions instead
+// of after each of them, which is what argument and generic lists need.
+//
+//     fn sum(#(x~N: u32),*)        fn sum(x0: u32, x1: u32, x2: u32)
+//
+// A section followed by a range in brackets repeats over that range instead of
+// the header. Nested in another section, the range may use the variables of
+// the enclosing sections, which gives triangular structures:
+//
+//     #( [#(K,)*[K in 0..N]] )*    [] [0,] [0, 1,]
+//
+// Once the body has a section, a loop variable outside of every section is an
+// error since it has no value there.
+
+use seq::seq;
+
+seq!(N in 0..3 {
+    fn sum(#(x~N: u32),*) -> u32 {
+        0 #(+ x~N)*
+    }
+
+    #[allow(dead_code)]
+    struct Tuple<#(T~N),*>(#(T~N),*);
+
+    const LEN: usize = 0 #(+ { let _ = N; 1 })*;
+});
+
+seq!(N in 0..4 {
+    // Row `N` holds the numbers below `N`.
+    const TRIANGLE: [&[usize]; 4] = [#( &[#(K),*[K in 0..N]] ),*];
+});
+
+seq!(N in 0..4 {
+    // The header variable rebound to a shorter range.
+    const FIRST: [usize; 2] = [#(N),*[0..2]];
+    const REST: [usize; 2] = [#(N),*[2..4]];
+});
+
+fn main() {
+    assert_eq!(sum(1, 2, 3), 6);
+    let _: Tuple<u8, u16, u32> = Tuple(1, 2, 3);
+    assert_eq!(LEN, 3);
+
+    let expected: [&[usize]; 4] = [&[], &[0], &[0, 1], &[0, 1, 2]];
+    assert_eq!(TRIANGLE, expected);
+
+    assert_eq!(FIRST, [0, 1]);
+    assert_eq!(REST, [2, 3]);
+}
@@ -0,0 +1,21 @@
+// Once the body has a repetition section only the sections are repeated, so a
+// loop variable outside of them is reported. A section nested in another one
+// has to say what it repeats over, and a section range without a variable
+// needs a header binding a single variable.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    const ALL: [usize; 4] = [#(N,)*];
+    const LAST: usize = N;
+});
+
+seq!(N in 0..4 {
+    const GRID: [[usize; 4]; 4] = [#([#(N,)*],)*];
+});
+
+seq!(I in 0..2, J in 0..2 {
+    const ROW: [usize; 2] = [#(I,)*[0..2]];
+});
+
+fn main() {}
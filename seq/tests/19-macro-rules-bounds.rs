@@ -0,0 +1,59 @@
+// The bounds of the range are constant expressions, so generator macros can
+// compute them. Metavariables other than `$n:literal`, such as `$n:expr` or a
+// `$n:tt` passed through another macro, reach `seq!` wrapped in an invisible
+// group, which is evaluated like a parenthesized expression.
+
+use seq::seq;
+
+macro_rules! last_index {
+    ($n:expr) => {
+        seq!(N in 0..$n - 1 {
+            const LAST: usize = #(N +)* 0;
+        });
+    };
+}
+
+last_index!(4);
+
+macro_rules! forward {
+    ($n:tt) => {
+        squares!($n);
+    };
+}
+
+macro_rules! squares {
+    ($n:expr) => {
+        seq!(N in 0..=$n {
+            const SQUARES: [u32; $n + 1] = [#(N * N,)*];
+        });
+    };
+}
+
+forward!(3);
+
+macro_rules! masks {
+    ($bits:expr) => {
+        seq!(N in (0..2 * $bits).step_by($bits) {
+            const MASKS: [u32; 2] = [#(1 << N,)*];
+        });
+    };
+}
+
+masks!(8);
+
+macro_rules! whole_range {
+    ($range:expr) => {
+        seq!(N in $range {
+            const COUNT: usize = 0 #(+ { let _ = N; 1 })*;
+        });
+    };
+}
+
+whole_range!(0..5);
+
+fn main() {
+    assert_eq!(LAST, 3);
+    assert_eq!(SQUARES, [0, 1, 4, 9]);
+    assert_eq!(MASKS, [1, 1 << 8]);
+    assert_eq!(COUNT, 5);
+}
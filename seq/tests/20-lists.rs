@@ -0,0 +1,72 @@
+// Besides ranges, a loop variable can go over an explicit list in brackets or
+// braces. Integer elements behave like the values of a range, anything else,
+// such as a type, replaces the variable as is and can be pasted when it is a
+// single identifier.
+//
+//     seq!(T in [u8, u16, u32, u64] { ... })
+//     seq!(N in {1, 2, 4, 8} { ... })
+//
+// `i, T in [...]` also binds `i` to the index of each element, and lists can
+// be zipped with ranges or other lists.
+
+use seq::seq;
+
+trait Width {
+    const BITS: u32;
+}
+
+seq!(T in [u8, u16, u32, u64] {
+    impl Width for T {
+        const BITS: u32 = T::BITS;
+    }
+});
+
+seq!(N in {1, 2, 4, 8} {
+    const SIZES: [usize; 4] = [#(#{N * 8},)*];
+});
+
+seq!(i, T in [u8, Vec<u8>, Option<(u8, u16)>] {
+    #(
+        #[allow(dead_code)]
+        type Alias~i = T;
+    )*
+    const COUNT: usize = 0 #(+ { let _ = i; 1 })*;
+});
+
+seq!(T in [Red, Green, Blue] {
+    #[derive(Debug, PartialEq)]
+    enum Color {
+        #(T,)*
+    }
+
+    impl Color {
+        #(
+            #[allow(non_snake_case)]
+            fn is_~T(&self) -> bool {
+                *self == Color::T
+            }
+        )*
+    }
+});
+
+seq!((A, B) in zip([x, y, z], {10, 20, 30}) {
+    #(
+        #[allow(non_upper_case_globals)]
+        const A: i32 = B;
+    )*
+});
+
+fn main() {
+    assert_eq!(<u16 as Width>::BITS, 16);
+    assert_eq!(<u64 as Width>::BITS, 64);
+    assert_eq!(SIZES, [8, 16, 32, 64]);
+
+    let _: Alias1 = vec![1, 2];
+    let _: Alias2 = Some((1, 2));
+    assert_eq!(COUNT, 3);
+
+    assert!(Color::Green.is_Green());
+    assert!(!Color::Red.is_Blue());
+
+    assert_eq!(x + y + z, 60);
+}
@@ -0,0 +1,31 @@
+// A `seq!` stops with an error once it expands to more repetitions than its
+// limit, 65536 unless the header raises or lowers it with `#![limit = N]`. The
+// repetitions of nested sections count too.
+//
+// Bounds can use the `MIN` and `MAX` of the integer types, even at the very end
+// of a type where computing one past the end bound would overflow.
+
+use seq::seq;
+
+seq!(#![limit = 20] N in 0..4 {
+    const TRIANGLE: usize = 0 #( #(+ K)*[K in 0..N] )*;
+});
+
+seq!(N in 0..=u8::MAX {
+    const BYTES: [u8; 256] = [#(N,)*];
+});
+
+seq!(N in u64::MAX - 2..=u64::MAX {
+    const TOP: [u64; 3] = [#(N,)*];
+});
+
+seq!(N in (i128::MIN..=i128::MIN + 1).rev() {
+    const BOTTOM: [i128; 2] = [#(N,)*];
+});
+
+fn main() {
+    assert_eq!(TRIANGLE, 4);
+    assert_eq!(BYTES[255], 255);
+    assert_eq!(TOP, [u64::MAX - 2, u64::MAX - 1, u64::MAX]);
+    assert_eq!(BOTTOM, [i128::MIN + 1, i128::MIN]);
+}
@@ -0,0 +1,31 @@
+// Repeating more than the limit is reported before anything is expanded, and a
+// range that ends before it starts is reported instead of expanding to nothing.
+// The options of the header take nothing more than their value.
+
+use seq::seq;
+
+seq!(N in 0..1000000 {
+    fn f~N() {}
+});
+
+seq!(#![limit = 8] N in 0..4 {
+    const TABLE: [[usize; 4]; 4] = [#([#(K,)*[K in 0..4]],)*];
+});
+
+seq!(N in 8..0 {
+    fn g~N() {}
+});
+
+seq!(N in 0..=u128::MAX {
+    fn h~N() {}
+});
+
+seq!(#![limit = 5 junk] N in 0..4 {
+    fn i~N() {}
+});
+
+seq!(#![debug whatever] N in 0..4 {
+    fn j~N() {}
+});
+
+fn main() {}
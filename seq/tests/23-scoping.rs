@@ -0,0 +1,82 @@
+// Every identifier equal to a loop variable is replaced, so a few escapes are
+// needed to keep one:
+//
+//     #N       the identifier `N` itself
+//     $N       a metavariable of a `macro_rules!` defined in the body
+//
+// A nested `seq!` that binds a variable with the same name shadows the outer
+// one in its whole invocation, the other outer variables being replaced as
+// usual, bounds and paste chains included. A chain using variables of both is
+// pasted by the nested `seq!` once the outer ones are replaced.
+
+use seq::seq;
+
+#[allow(non_snake_case)]
+struct Pair {
+    N: u8,
+}
+
+seq!(N in 1..3 {
+    #[allow(non_snake_case)]
+    fn field~N(pair: &Pair) -> u8 {
+        pair.#N * N
+    }
+});
+
+seq!(N in 0..2 {
+    macro_rules! add~N {
+        ($N:expr) => {
+            $N + N
+        };
+    }
+});
+
+seq!(N in 0..1 {
+    #(
+        seq!(N in 10..12 {
+            const INNER: [u32; 2] = [#(N,)*];
+        });
+    )*
+});
+
+seq!(N in 1..3 {
+    #(
+        seq!(K in 0..N {
+            const ROW~N: [u32; N] = [#(K,)*];
+        });
+    )*
+});
+
+seq!(I in 0..2 {
+    seq!(J in 0..2 {
+        #(
+            const X~I~J: u32 = I * 10 + J;
+        )*
+    });
+});
+
+seq!(I in 0..2 {
+    seq!(J in 0..2 {
+        #(
+            const Y~J~I:02: u32 = J * 10 + I;
+        )*
+    });
+});
+
+#[allow(non_snake_case)]
+fn main() {
+    let pair = Pair { N: 5 };
+    assert_eq!(field1(&pair), 5);
+    assert_eq!(field2(&pair), 10);
+
+    assert_eq!(add0!(3), 3);
+    assert_eq!(add1!(3), 4);
+
+    assert_eq!(INNER, [10, 11]);
+
+    assert_eq!(ROW1, [0]);
+    assert_eq!(ROW2, [0, 1]);
+
+    assert_eq!([X00, X01, X10, X11], [0, 1, 10, 11]);
+    assert_eq!([Y001, Y101], [1, 11]);
+}
@@ -0,0 +1,68 @@
+// `#[repeat(...)]` is the attribute form of `seq!`, for generating variants,
+// fields or associated items while keeping the surrounding item valid Rust,
+// which tools like rustfmt need. It takes the same header as `seq!`, and
+// repeats the elements marked with `#[each]`:
+//
+//     #[repeat(N in 0..16)]
+//     enum Irq {
+//         #[each(Irq~N)]
+//         Irq,
+//         Spurious,
+//     }
+//
+// The copies have their loop variables replaced, and `#[each(...)]` gives the
+// name that replaces the one the element is written with. Unmarked elements
+// are kept once, as they are: a `#(...)*` in them is left to whatever macro it
+// is written for.
+
+use seq::repeat;
+
+#[repeat(N in 0..16)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Irq {
+    #[each(Irq~N)]
+    Irq = N,
+    Spurious = 16,
+}
+
+#[repeat(N in 0..4)]
+#[derive(Default)]
+struct Registers {
+    #[each(reg~N)]
+    reg: [u8; N + 1],
+    status: u32,
+}
+
+#[repeat(T in [u8, u16, u32])]
+struct Tuple(#[each] Vec<T>, bool);
+
+#[repeat(#![limit = 8] N in 0..4)]
+impl Registers {
+    #[each(get~N)]
+    fn get(&self) -> u8 {
+        self.status as u8 + N
+    }
+
+    #[each(SIZE_~N)]
+    const SIZE: usize = N + 1;
+
+    fn pattern() -> &'static str {
+        stringify!(#(x)*)
+    }
+}
+
+fn main() {
+    assert_eq!(Irq::Irq3 as u8, 3);
+    assert_ne!(Irq::Irq15, Irq::Spurious);
+
+    let registers = Registers::default();
+    assert_eq!(registers.status, 0);
+    assert_eq!(registers.reg3.len(), 4);
+    assert_eq!(registers.get0(), 0);
+    assert_eq!(registers.get3(), 3);
+    assert_eq!(Registers::SIZE_2, 3);
+    assert_eq!(Registers::pattern().replace(' ', ""), "#(x)*");
+
+    let tuple = Tuple(vec![1u8], vec![2u16], vec![3u32], true);
+    assert!(tuple.3);
+}
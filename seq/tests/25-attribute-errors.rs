@@ -0,0 +1,21 @@
+// A loop variable used outside of the elements marked with `#[each]` has no
+// value, and an attribute without any marked element is most likely a mistake.
+
+use seq::repeat;
+
+#[repeat(N in 0..4)]
+enum Irq {
+    #[each(Irq~N)]
+    Irq,
+    Last = N,
+}
+
+#[repeat(N in 0..4)]
+struct Registers {
+    reg: u32,
+}
+
+#[repeat(N in 0..4)]
+struct Tuple(#[each(field~N)] u8);
+
+fn main() {}
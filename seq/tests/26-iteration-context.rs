@@ -0,0 +1,27 @@
+// An error raised by `seq!` while expanding one of the iterations tells which
+// one, since the template alone doesn't say. The errors that rustc reports in
+// the expanded code, like the four of test 03, can't carry that note on stable
+// Rust. For those, `#![debug]` in the header prints the expansion while
+// compiling to diagnose the generated code. The printed text is checked by
+// tests/debug.rs since it isn't part of the compiler diagnostics.
+
+use seq::seq;
+
+seq!(N in 254u8..=255 {
+    const NEXT~N: u8 = #{N + 1};
+});
+
+seq!(I in 0..2, J in 0..2 {
+    #(
+        fn f~{I - J}() {}
+    )*
+});
+
+seq!(#![debug] N in 0..2 {
+    #[allow(dead_code)]
+    fn g~N() -> u8 {
+        N * 2
+    }
+});
+
+fn main() {}
@@ -0,0 +1,42 @@
+// `#![debug]` prints the expansion to the standard error of the compiler,
+// which trybuild leaves out of the diagnostics it compares. The printed text is
+// checked here instead, expanding outside of the compiler like the benchmark.
+
+#![allow(dead_code)]
+
+#[path = "../src/binding.rs"]
+mod binding;
+#[path = "../src/expr.rs"]
+mod expr;
+#[path = "../src/item.rs"]
+mod item;
+#[path = "../src/list.rs"]
+mod list;
+#[path = "../src/range.rs"]
+mod range;
+#[path = "../src/sequence.rs"]
+mod sequence;
+#[path = "../src/template.rs"]
+mod template;
+
+use sequence::Sequence;
+
+fn report(input: &str) -> Option<String> {
+    let mut seq = syn::parse_str::<Sequence>(input).unwrap();
+    let tokens = seq.expand().unwrap();
+    seq.debug_report("seq!", &tokens)
+}
+
+#[test]
+fn prints_expansion() {
+    let report = report("#![debug] N in 0..2 { fn g~N() -> u8 { N * 2 } }");
+    assert_eq!(
+        report.as_deref(),
+        Some("seq! expanded to:\nfn g0 () -> u8 { 0 * 2 } fn g1 () -> u8 { 1 * 2 }"),
+    );
+}
+
+#[test]
+fn prints_nothing_without_debug() {
+    assert_eq!(report("N in 0..2 { fn g~N() {} }"), None);
+}
@@ -10,4 +10,21 @@ fn tests() {
     t.pass("tests/07-inclusive-range.rs");
     t.compile_fail("tests/08-ident-span.rs");
     t.pass("tests/09-interaction-with-macrorules.rs");
+    t.pass("tests/10-stepped-range.rs");
+    t.pass("tests/11-literal-kind.rs");
+    t.pass("tests/12-multiple-bindings.rs");
+    t.pass("tests/13-const-expr.rs");
+    t.compile_fail("tests/14-const-expr-overflow.rs");
+    t.pass("tests/15-paste-chains.rs");
+    t.compile_fail("tests/16-invalid-paste.rs");
+    t.pass("tests/17-repetition-sections.rs");
+    t.compile_fail("tests/18-mixed-sections.rs");
+    t.pass("tests/19-macro-rules-bounds.rs");
+    t.pass("tests/20-lists.rs");
+    t.pass("tests/21-expansion-limit.rs");
+    t.compile_fail("tests/22-limit-exceeded.rs");
+    t.pass("tests/23-scoping.rs");
+    t.pass("tests/24-attribute.rs");
+    t.compile_fail("tests/25-attribute-errors.rs");
+    t.compile_fail("tests/26-iteration-context.rs");
 }